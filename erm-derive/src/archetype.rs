@@ -1,27 +1,54 @@
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
-use syn::{parse::Parse, Data, DeriveInput, Error};
+use syn::{parse::Parse, Data, DeriveInput, Error, Type};
 
 use crate::field::Field;
 
 pub struct Archetype {
     pub typename: Ident,
     pub fields: Vec<Field>,
+    /// Fields marked `#[erm(skip)]`, which are not components.
+    pub skipped: Vec<Field>,
+    /// Variants of enum archetypes, each holding a single archetype, or `None` for structs.
+    ///
+    /// Variants cannot share components, since columns are named after their component's table.
+    pub variants: Option<Vec<(Ident, Type)>>,
 }
 
 impl Archetype {
+    /// Types of the archetypes contained in the fields or variants.
+    fn typenames(&self) -> Vec<&Type> {
+        match &self.variants {
+            Some(variants) => variants.iter().map(|(_, typename)| typename).collect(),
+            None => self.fields.iter().map(Field::typename).collect(),
+        }
+    }
+
     pub fn implementation(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let archetype_name = &self.typename;
 
         let remove = self.remove(sqlx, database);
 
-        let deserializer = self.component_deserializer(sqlx, database);
+        let (serializer, deserializer) = match &self.variants {
+            Some(variants) => (
+                self.variant_serializer(variants, sqlx, database),
+                self.variant_deserializer(variants, sqlx, database),
+            ),
+            None => (
+                self.component_serializer(sqlx, database),
+                self.component_deserializer(sqlx, database),
+            ),
+        };
 
         quote! {
             impl ::erm::archetype::Archetype<#database> for #archetype_name
             {
             }
 
+            impl ::erm::serialization::Serializable<#database> for #archetype_name {
+                #serializer
+            }
+
             impl ::erm::serialization::Deserializeable<#database> for #archetype_name {
                 #deserializer
             }
@@ -33,14 +60,18 @@ impl Archetype {
     }
 
     fn remove(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
-        let sub_archetypes = self.fields.iter().map(|field| {
-            let typename = field.typename();
-
+        let sub_archetypes = self.typenames().into_iter().map(|typename| {
             quote! {
                 <#typename as ::erm::tables::Removable<#database>>::remove(query);
             }
         });
 
+        let sub_hooks = self.typenames().into_iter().map(|typename| {
+            quote! {
+                <#typename as ::erm::tables::Removable<#database>>::on_remove(entity.clone(), &mut *connection).await?;
+            }
+        });
+
         quote! {
             fn remove<'query, EntityId>(query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
             where
@@ -48,6 +79,271 @@ impl Archetype {
             {
                 #(#sub_archetypes)*
             }
+
+            fn on_remove<'hook, EntityId>(
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                async move {
+                    #(#sub_hooks)*
+
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Delegates serialization to each of the contained components, in field order.
+    fn component_serializer(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let serializers = self.fields.iter().map(|field| {
+            let name = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                let query = <#typename as ::erm::serialization::Serializable<#database>>::serialize(&self.#name, query);
+            }
+        });
+
+        let inserts = self.fields.iter().map(|field| {
+            let name = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::erm::serialization::Serializable<#database>>::insert(&self.#name, query);
+            }
+        });
+
+        let updates = self.fields.iter().map(|field| {
+            let name = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::erm::serialization::Serializable<#database>>::update(&self.#name, query);
+            }
+        });
+
+        let insert_hooks = self.fields.iter().map(|field| {
+            let name = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::erm::serialization::Serializable<#database>>::on_insert(&self.#name, entity.clone(), &mut *connection).await?;
+            }
+        });
+
+        let update_hooks = self.fields.iter().map(|field| {
+            let name = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::erm::serialization::Serializable<#database>>::on_update(&self.#name, entity.clone(), &mut *connection).await?;
+            }
+        });
+
+        quote! {
+            fn serialize<'q>(
+                &'q self,
+                query: #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>>,
+            ) -> #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>> {
+                #(#serializers)*
+
+                query
+            }
+
+            fn insert<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #(#inserts)*
+            }
+
+            fn update<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #(#updates)*
+            }
+
+            fn on_insert<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                async move {
+                    #(#insert_hooks)*
+
+                    Ok(())
+                }
+            }
+
+            fn on_update<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                async move {
+                    #(#update_hooks)*
+
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Delegates serialization to the archetype held by the current variant.
+    ///
+    /// Only the components of that variant are written, so updating an entity
+    /// to a different variant requires removing the archetype and inserting it again.
+    fn variant_serializer(
+        &self,
+        variants: &[(Ident, Type)],
+        sqlx: &TokenStream,
+        database: &TokenStream,
+    ) -> TokenStream {
+        let delegate = |method: TokenStream, arguments: TokenStream, suffix: TokenStream| {
+            let arms = variants.iter().map(|(ident, typename)| {
+                quote! {
+                    Self::#ident(inner) => <#typename as ::erm::serialization::Serializable<#database>>::#method(inner, #arguments)#suffix
+                }
+            });
+
+            quote! {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        };
+
+        let serialize = delegate(quote! { serialize }, quote! { query }, quote! {});
+        let insert = delegate(quote! { insert }, quote! { query }, quote! {});
+        let update = delegate(quote! { update }, quote! { query }, quote! {});
+        let on_insert = delegate(
+            quote! { on_insert },
+            quote! { entity, connection },
+            quote! { .await },
+        );
+        let on_update = delegate(
+            quote! { on_update },
+            quote! { entity, connection },
+            quote! { .await },
+        );
+
+        quote! {
+            fn serialize<'q>(
+                &'q self,
+                query: #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>>,
+            ) -> #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>> {
+                #serialize
+            }
+
+            fn insert<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #insert
+            }
+
+            fn update<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #update
+            }
+
+            fn on_insert<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                async move {
+                    #on_insert
+                }
+            }
+
+            fn on_update<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                async move {
+                    #on_update
+                }
+            }
+        }
+    }
+
+    /// Reads each variant as optional, constructing the first one present.
+    ///
+    /// Entities matching several variants are returned as the first of them.
+    fn variant_deserializer(
+        &self,
+        variants: &[(Ident, Type)],
+        sqlx: &TokenStream,
+        database: &TokenStream,
+    ) -> TokenStream {
+        let sub_expressions = variants.iter().map(|(_, typename)| {
+            quote! {
+                <#typename as ::erm::serialization::Deserializeable<#database>>::cte()
+            }
+        });
+
+        let bindings: Vec<_> = (0..variants.len())
+            .map(|index| Ident::new(&format!("variant{index}"), Span::call_site()))
+            .collect();
+
+        let components = variants.iter().zip(&bindings).map(|((_, typename), binding)| {
+            quote! {
+                let #binding = <Option<#typename> as ::erm::serialization::Deserializeable<#database>>::deserialize(row)?;
+            }
+        });
+
+        let arms = variants.iter().zip(&bindings).map(|((ident, _), binding)| {
+            quote! {
+                if let Some(inner) = #binding {
+                    return Ok(Self::#ident(inner));
+                }
+            }
+        });
+
+        let archetype_name = self.typename.to_string();
+
+        quote! {
+            fn cte() -> Box<dyn ::erm::cte::CommonTableExpression> {
+                Box::new(::erm::cte::OneOf {
+                    variants: vec![
+                        #(#sub_expressions,)*
+                    ]
+                })
+            }
+
+            fn deserialize(row: &mut ::erm::row::OffsetRow<<#database as #sqlx::Database>::Row>) -> Result<Self, #sqlx::Error> {
+                #(#components)*
+
+                #(#arms)*
+
+                Err(#sqlx::Error::Decode(
+                    format!("entity matches no variant of {}", #archetype_name).into(),
+                ))
+            }
         }
     }
 
@@ -63,7 +359,7 @@ impl Archetype {
         });
 
         let components = self.fields.iter().map(|field| {
-            let name = field.ident();
+            let name = field.field_name();
             let typename = field.typename();
 
             quote! {
@@ -71,13 +367,11 @@ impl Archetype {
             }
         });
 
-        let assignments = self.fields.iter().map(|field| {
-            let ident = field.ident();
-
-            quote! {
-                #ident: #ident?
-            }
-        });
+        let assignments = self
+            .fields
+            .iter()
+            .chain(&self.skipped)
+            .map(Field::assignment);
 
         quote! {
             fn cte() -> Box<dyn ::erm::cte::CommonTableExpression> {
@@ -105,22 +399,48 @@ impl Parse for Archetype {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let derive = DeriveInput::parse(input)?;
 
-        let Data::Struct(data) = derive.data else {
-            return Err(Error::new(
-                derive.ident.span(),
-                "Archetype can only be derived for struct types",
-            ));
-        };
-
         let type_name = derive.ident.clone();
 
-        let fields = Result::<Vec<Field>, _>::from_iter(
-            data.fields.into_iter().enumerate().map(Field::try_from),
-        )?;
+        match derive.data {
+            Data::Struct(data) => {
+                let (skipped, fields) = Result::<Vec<Field>, _>::from_iter(
+                    data.fields.into_iter().enumerate().map(Field::try_from),
+                )?
+                .into_iter()
+                .partition(Field::skip);
+
+                Ok(Archetype {
+                    typename: type_name,
+                    fields,
+                    skipped,
+                    variants: None,
+                })
+            }
+            Data::Enum(data) if !data.variants.is_empty() => {
+                let variants = Result::<Vec<_>, _>::from_iter(data.variants.into_iter().map(
+                    |variant| match variant.fields {
+                        syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            let field = fields.unnamed.into_iter().next().unwrap();
+                            Ok((variant.ident, field.ty))
+                        }
+                        _ => Err(Error::new(
+                            variant.ident.span(),
+                            "Archetype variants must hold a single archetype, such as `Circle(Circle)`",
+                        )),
+                    },
+                ))?;
 
-        Ok(Archetype {
-            typename: type_name,
-            fields,
-        })
+                Ok(Archetype {
+                    typename: type_name,
+                    fields: Vec::new(),
+                    skipped: Vec::new(),
+                    variants: Some(variants),
+                })
+            }
+            _ => Err(Error::new(
+                derive.ident.span(),
+                "Archetype can only be derived for structs and enums with at least one variant",
+            )),
+        }
     }
 }
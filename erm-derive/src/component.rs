@@ -1,13 +1,42 @@
 use proc_macro2::{Ident, Literal, Punct, TokenStream};
 use quote::quote;
-use syn::{parse::Parse, spanned::Spanned, Data, DeriveInput, Error, Token};
+use syn::{
+    parse::Parse, spanned::Spanned, Data, DeriveInput, Error, GenericParam, Generics, Token,
+    WherePredicate,
+};
 
-use crate::field::Field;
+use crate::{field::Field, variant::Variant};
 
 pub struct Component {
     pub typename: Ident,
+    pub generics: Generics,
+    /// Name of the component's table. For generic components, this may be a template
+    /// such as `tagged_{T}`, which is resolved for each instantiation at runtime.
     pub table_name: String,
+    /// Columns of the component. For enums, these are the flattened columns of all variants.
     pub fields: Vec<Field>,
+    /// Fields marked `#[erm(skip)]`, which have no column.
+    pub skipped: Vec<Field>,
+    /// Fields marked `#[erm(flatten)]`, whose columns follow those of the other fields.
+    pub flattened: Vec<Field>,
+    /// Fields marked `#[erm(collection)]`, whose elements are stored in child tables.
+    pub collections: Vec<Field>,
+    /// Variants of enum components, or `None` for structs.
+    pub variants: Option<Vec<Variant>>,
+    pub track_changes: bool,
+    pub hooks: bool,
+    pub changelog: bool,
+    pub history: bool,
+    pub soft_delete: bool,
+    pub versioned: bool,
+    /// Indexes created along with the table, from both field and component attributes.
+    pub indexes: Vec<Index>,
+}
+
+/// Index on one or more columns of a component's table.
+pub struct Index {
+    pub columns: Vec<String>,
+    pub unique: bool,
 }
 
 impl Component {
@@ -18,45 +47,273 @@ impl Component {
         placeholder_char: char,
     ) -> TokenStream {
         let component_name = &self.typename;
+        let (impl_generics, ty_generics, where_clause) = self.split_generics(sqlx, database);
 
-        let statements = self.statements(placeholder_char);
+        let statements = self.statements(database, placeholder_char);
         let table = self.table();
         let columns = self.columns(sqlx, database);
         let table_creator = self.table_creator(sqlx, database);
-        let remove = self.remove(sqlx, database);
-        let insert = self.insert(sqlx, database);
-        let update = self.update(database);
+        let (remove_component, remove) = self.remove(sqlx, database);
+        let insert = self.insert(sqlx, database, placeholder_char);
+        let update = self.update(sqlx, database, placeholder_char);
         let serialize = self.field_serializer(sqlx, database);
         let deserialize = self.field_deserializer(sqlx, database);
+        let (insert_hooks, remove_hooks) = self.hooks(sqlx, database);
+        let soft_delete = self.soft_delete(sqlx, database, placeholder_char);
+        let versioned = self.versioned(sqlx, database, placeholder_char);
 
         quote! {
-            impl ::erm::component::Component<#database> for #component_name {
+            impl #impl_generics ::erm::component::Component<#database> for #component_name #ty_generics #where_clause {
                 #statements
                 #table
                 #columns
                 #table_creator
+                #remove_component
             }
 
-            impl ::erm::archetype::Archetype<#database> for #component_name {}
+            impl #impl_generics ::erm::archetype::Archetype<#database> for #component_name #ty_generics #where_clause {}
 
-            impl ::erm::serialization::Serializable<#database> for #component_name {
+            impl #impl_generics ::erm::serialization::Serializable<#database> for #component_name #ty_generics #where_clause {
                 #serialize
                 #insert
                 #update
+                #insert_hooks
             }
 
-            impl ::erm::serialization::Deserializeable<#database> for #component_name {
+            impl #impl_generics ::erm::serialization::Deserializeable<#database> for #component_name #ty_generics #where_clause {
                 #deserialize
             }
 
-            impl ::erm::tables::Removable<#database> for #component_name {
+            impl #impl_generics ::erm::tables::Removable<#database> for #component_name #ty_generics #where_clause {
                 #remove
+                #remove_hooks
             }
+
+            #soft_delete
+            #versioned
+        }
+    }
+
+    /// Generics of the generated impls, bounding the types of fields which depend on the
+    /// type parameters so that they can be read from and written to `database`.
+    pub fn split_generics(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+    ) -> (TokenStream, TokenStream, TokenStream) {
+        let mut generics = self.generics.clone();
+
+        let predicates: Vec<WherePredicate> = self
+            .fields
+            .iter()
+            .map(|field| field.intermediate().unwrap_or(field.typename()))
+            .filter(|typename| self.is_generic(typename))
+            .map(|typename| {
+                syn::parse_quote! {
+                    #typename: for<'r> #sqlx::Decode<'r, #database> + for<'q> #sqlx::Encode<'q, #database> + #sqlx::Type<#database>
+                }
+            })
+            .collect();
+
+        generics.make_where_clause().predicates.extend(predicates);
+
+        self.split(generics)
+    }
+
+    /// Splits `generics` for use in impls, requiring type parameters to be `'static`
+    /// if the table name depends on them, since it is interned per type.
+    pub fn split(&self, mut generics: Generics) -> (TokenStream, TokenStream, TokenStream) {
+        if self.generic_table() {
+            for parameter in generics.type_params_mut() {
+                parameter.bounds.push(syn::parse_quote!('static));
+            }
+        }
+
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        (
+            quote! { #impl_generics },
+            quote! { #ty_generics },
+            quote! { #where_clause },
+        )
+    }
+
+    /// Determines if `typename` refers to any of the component's type parameters.
+    fn is_generic(&self, typename: &syn::Type) -> bool {
+        fn contains(tokens: TokenStream, parameters: &[&Ident]) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Ident(ident) => parameters.contains(&&ident),
+                proc_macro2::TokenTree::Group(group) => contains(group.stream(), parameters),
+                _ => false,
+            })
+        }
+
+        let parameters: Vec<_> = self
+            .generics
+            .type_params()
+            .map(|parameter| &parameter.ident)
+            .collect();
+
+        !parameters.is_empty() && contains(quote! { #typename }, &parameters)
+    }
+
+    /// Whether the table name is a template depending on the type parameters.
+    pub fn generic_table(&self) -> bool {
+        self.table_name.contains('{')
+    }
+
+    /// Table name to use within statements, which is substituted by [`Self::statement`]
+    /// for generic tables.
+    fn table_placeholder(&self) -> &str {
+        if self.generic_table() {
+            "{table}"
+        } else {
+            &self.table_name
         }
     }
 
-    fn statements(&self, placeholder_char: char) -> TokenStream {
-        let table = &self.table_name.trim_matches('"');
+    /// Expression evaluating to the name of the component's table, independent of the database.
+    pub fn table_expression(&self) -> TokenStream {
+        let table_name = &self.table_name;
+
+        if !self.generic_table() {
+            return quote! { #table_name };
+        }
+
+        let component_name = &self.typename;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+
+        let arguments = self.generics.params.iter().filter_map(|parameter| {
+            let (ident, value) = match parameter {
+                GenericParam::Type(parameter) => {
+                    let ident = &parameter.ident;
+                    (ident, quote! { ::erm::component::type_name::<#ident>() })
+                }
+                GenericParam::Const(parameter) => {
+                    let ident = &parameter.ident;
+                    (ident, quote! { #ident })
+                }
+                GenericParam::Lifetime(_) => return None,
+            };
+
+            table_name
+                .contains(&format!("{{{ident}}}"))
+                .then(|| quote! { #ident = #value })
+        });
+
+        quote! {
+            ::erm::component::intern::<#component_name #ty_generics>("table", || format!(#table_name, #(#arguments),*))
+        }
+    }
+
+    /// Whether statements are templates, which are completed at runtime.
+    fn runtime_statements(&self) -> bool {
+        self.generic_table() || !self.flattened.is_empty()
+    }
+
+    /// Associated constant `name` holding `statement`, along with the method completing
+    /// it at runtime if it is a template, such as `INSERT` and `insert_statement`.
+    fn statement_items(
+        &self,
+        database: &TokenStream,
+        placeholder_char: char,
+        name: &str,
+        statement: String,
+    ) -> TokenStream {
+        let constant = Ident::new(name, self.typename.span());
+
+        let completion = if self.runtime_statements() {
+            let key = name.to_lowercase();
+            let method = Ident::new(&format!("{key}_statement"), self.typename.span());
+            let value = self.statement(database, placeholder_char, &key, statement.clone());
+
+            quote! {
+                fn #method() -> &'static str {
+                    #value
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
+
+        quote! {
+            const #constant: &'static str = #statement;
+            #completion
+        }
+    }
+
+    /// Statement which is a constant, unless the table name is generic or the component
+    /// has flattened fields, in which case it is formatted once per instantiation.
+    fn statement(
+        &self,
+        database: &TokenStream,
+        placeholder_char: char,
+        key: &str,
+        statement: String,
+    ) -> TokenStream {
+        if !self.runtime_statements() {
+            return quote! { #statement };
+        }
+
+        let table = self.table_argument(database);
+
+        // Flattened columns are bound after the fields, which are bound after the entity.
+        let first = self.fields.len() + 2;
+
+        let arguments: Vec<_> = [
+            ("flattened_names", quote! { flattened.list() }),
+            (
+                "flattened_values",
+                quote! { flattened.placeholders(#placeholder_char, #first) },
+            ),
+            (
+                "flattened_assignments",
+                quote! { flattened.assignments(#placeholder_char, #first) },
+            ),
+            (
+                "version_placeholder",
+                quote! { format!("{}{}", #placeholder_char, #first + flattened.count()) },
+            ),
+        ]
+        .into_iter()
+        .filter(|(name, _)| statement.contains(&format!("{{{name}}}")))
+        .map(|(name, value)| {
+            let name = Ident::new(name, self.typename.span());
+            quote! { #name = #value, }
+        })
+        .collect();
+
+        let flattened = if arguments.is_empty() {
+            TokenStream::new()
+        } else {
+            let columns = self.flattened_columns(database);
+            quote! { let flattened = #columns; }
+        };
+
+        quote! {
+            ::erm::component::intern::<(Self, #database)>(#key, || {
+                #flattened
+                format!(#statement, #table #(#arguments)*)
+            })
+        }
+    }
+
+    /// Expression evaluating to the `erm::flatten::FlattenedColumns` of the flattened fields.
+    fn flattened_columns(&self, database: &TokenStream) -> TokenStream {
+        let fields = self.flattened.iter().map(|field| {
+            let typename = field.typename();
+            let prefix = field.prefix();
+
+            quote! { .field::<#typename>(#prefix) }
+        });
+
+        quote! {
+            ::erm::flatten::FlattenedColumns::<#database>::new()#(#fields)*
+        }
+    }
+
+    fn statements(&self, database: &TokenStream, placeholder_char: char) -> TokenStream {
+        let table = self.table_placeholder();
 
         let column_names: Vec<_> = self
             .fields
@@ -66,53 +323,195 @@ impl Component {
 
         let placeholders = placeholders(placeholder_char, column_names.len() + 1);
 
+        let tick = self.tick();
+
+        let mut generated = Vec::new();
+
+        if self.track_changes {
+            generated.push(("added_at", tick.clone()));
+            generated.push(("changed_at", tick.clone()));
+        }
+
+        if self.versioned {
+            generated.push(("version", "1".to_string()));
+        }
+
+        let (flattened_names, flattened_values) = if self.flattened.is_empty() {
+            ("", "")
+        } else {
+            ("{flattened_names}", "{flattened_values}")
+        };
+
         let insert = format!(
-            "insert into {table}(entity{column_names}) values({placeholders});",
+            "insert into {table}(entity{column_names}{flattened_names}{generated_names}) values({placeholders}{flattened_values}{generated_values});",
             placeholders = placeholders.join(", "),
-            column_names = column_names.join("")
+            column_names = column_names.join(""),
+            generated_names = generated
+                .iter()
+                .map(|(name, _)| format!(", {name}"))
+                .collect::<String>(),
+            generated_values = generated
+                .iter()
+                .map(|(_, value)| format!(", {value}"))
+                .collect::<String>(),
         );
 
-        let update = {
-            let field_updates = column_names
-                .iter()
-                .zip(placeholders.iter().skip(1))
-                .map(|(column, placeholder)| format!("{column} = {placeholder}"))
-                .collect::<Vec<_>>();
+        let update = self.update_statement(placeholder_char, self.versioned);
 
-            format!(
-                "update {table} set {field_updates} where entity = {placeholder_char}1",
-                field_updates = field_updates.join(", ")
+        let delete = if self.soft_delete {
+            format!("update {table} set deleted_at = current_timestamp where entity = {placeholder_char}1 and deleted_at is null")
+        } else {
+            format!("delete from {table} where entity = {placeholder_char}1")
+        };
+
+        let changelog = self.changelog;
+
+        let insert = self.statement_items(database, placeholder_char, "INSERT", insert);
+        let update = self.statement_items(database, placeholder_char, "UPDATE", update);
+        let delete = self.statement_items(database, placeholder_char, "DELETE", delete);
+
+        quote! {
+            const CHANGELOG: bool = #changelog;
+
+            #insert
+            #update
+            #delete
+        }
+    }
+
+    /// Statement updating the fields of the component, bound after the entity.
+    ///
+    /// If `expected_version` is set, the update only applies to the version bound after the fields.
+    fn update_statement(&self, placeholder_char: char, expected_version: bool) -> String {
+        let table = self.table_placeholder();
+
+        let mut field_updates = self
+            .fields
+            .iter()
+            .zip(
+                placeholders(placeholder_char, self.fields.len() + 1)
+                    .iter()
+                    .skip(1),
             )
+            .map(|(field, placeholder)| format!("{} = {placeholder}", field.column_name()))
+            .collect::<Vec<_>>();
+
+        if !self.flattened.is_empty() {
+            field_updates.push("{flattened_assignments}".to_string());
+        }
+
+        if self.track_changes {
+            field_updates.push(format!("changed_at = {}", self.tick()));
+        }
+
+        if self.versioned {
+            field_updates.push("version = version + 1".to_string());
+        }
+
+        let live = if self.soft_delete {
+            " and deleted_at is null"
+        } else {
+            ""
+        };
+
+        let current = if expected_version && !self.flattened.is_empty() {
+            " and version = {version_placeholder}".to_string()
+        } else if expected_version {
+            format!(" and version = {placeholder_char}{}", self.fields.len() + 2)
+        } else {
+            String::new()
         };
 
-        let delete = format!("delete from {table} where entity = {placeholder_char}1");
+        format!(
+            "update {table} set {field_updates} where entity = {placeholder_char}1{live}{current}",
+            field_updates = field_updates.join(", ")
+        )
+    }
+
+    /// Expression evaluating to the tick advanced by [`Self::advance_tick`].
+    fn tick(&self) -> String {
+        "(select tick from erm_ticks)".to_string()
+    }
+
+    /// Advances the tick shared by all tracked components, before the statements of a
+    /// component which opted in using `#[erm(track_changes)]` read it.
+    fn advance_tick(&self) -> TokenStream {
+        if !self.track_changes {
+            return TokenStream::new();
+        }
+
+        quote! {
+            query.statement("update erm_ticks set tick = tick + 1");
+        }
+    }
+
+    /// Appends the creation of the `erm_ticks` table to `sql`, if the component
+    /// opted in using `#[erm(track_changes)]`.
+    fn ticks_table_creator(&self, database: &TokenStream) -> TokenStream {
+        if !self.track_changes {
+            return TokenStream::new();
+        }
 
         quote! {
-            const INSERT: &'static str = #insert;
-            const UPDATE: &'static str = #update;
-            const DELETE: &'static str = #delete;
+            let sql = sql + "\n" + <#database as ::erm::component::Ticks>::CREATE_TABLE;
         }
     }
 
     fn table_creator(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
-        let table = &self.table_name.trim_matches('"');
+        let table = self.table_placeholder();
 
         let columns = self
             .fields
             .iter()
-            .map(Field::column_name)
-            .map(|column| format!(",\n  {column} {{}} {{}}"))
+            .map(|field| {
+                let column = field.column_name();
+
+                if field.cascade() {
+                    format!(",\n  {column} {{}} {{}} references {{}}(entity) on delete cascade")
+                } else if field.references().is_some() {
+                    format!(",\n  {column} {{}} {{}} references {{}}(entity)")
+                } else {
+                    format!(",\n  {column} {{}} {{}}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("");
+
+        let tracking = self
+            .tracking_columns()
+            .iter()
+            .map(|column| format!(",\n  {column} bigint not null"))
             .collect::<Vec<_>>()
             .join("");
 
-        let format_str =
-            format!("create table if not exists {table}(\n  entity {{}} primary key{columns}\n);");
+        let deleted_at = if self.soft_delete {
+            ",\n  deleted_at timestamp null"
+        } else {
+            ""
+        };
+
+        let flattened = self.flattened_definitions(database);
+
+        let format_str = format!(
+            "create table if not exists {table}(\n  entity {{}} primary key{columns}{flattened}{tracking}{deleted_at}\n);",
+            flattened = flattened.0,
+        );
 
         let definitions = self
             .fields
             .iter()
             .map(|field| field.sql_definition(sqlx, database));
 
+        let history = self.history_table_creator(sqlx, database);
+        let collection_tables = self
+            .collections
+            .iter()
+            .map(|field| self.collection_table_creator(sqlx, database, field));
+        let table_argument = self.table_argument(database);
+        let flattened_argument = flattened.1;
+        let indexes = self.index_creator(database);
+        let ticks = self.ticks_table_creator(database);
+
         quote! {
             fn create_component_table<'pool, EntityId>(
                 pool: &'pool #sqlx::Pool<#database>,
@@ -128,16 +527,211 @@ impl Component {
                         #format_str,
                         <EntityId as #sqlx::Type<#database>>::type_info().name(),
                         #(#definitions,)*
+                        #table_argument
+                        #flattened_argument
                     );
 
+                    #history
+                    #(#collection_tables)*
+                    #indexes
+                    #ticks
+
                     pool.execute(sql.as_str()).await
                 }
             }
         }
     }
 
+    /// Appends the creation of the component's indexes to `sql`, if it has any.
+    fn index_creator(&self, database: &TokenStream) -> TokenStream {
+        if self.indexes.is_empty() {
+            return TokenStream::new();
+        }
+
+        let table = if self.generic_table() {
+            quote! { <Self as ::erm::component::Component<#database>>::table() }
+        } else {
+            let table_name = &self.table_name;
+            quote! { #table_name }
+        };
+
+        let indexes = self.indexes.iter().map(|index| {
+            let columns = &index.columns;
+            let unique = index.unique;
+
+            quote! {
+                let sql = sql + "\n" + &<#database as ::erm::index::CreateIndex>::create_index(#table, &[#(#columns),*], #unique);
+            }
+        });
+
+        quote! {
+            #(#indexes)*
+        }
+    }
+
+    /// Appends the creation of the `<table>_history` table to `sql`, if the component
+    /// opted in using `#[erm(history)]`.
+    ///
+    /// Versions are not unique per entity, so the table has no primary key, but they
+    /// are indexed by entity and start of validity for looking up the version at a time.
+    fn history_table_creator(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        if !self.history {
+            return TokenStream::new();
+        }
+
+        let table = self.table_placeholder();
+
+        let columns = self
+            .fields
+            .iter()
+            .map(|field| format!(",\n  {} {{}} {{}}", field.column_name()))
+            .collect::<Vec<_>>()
+            .join("");
+
+        let tracking = self
+            .tracking_columns()
+            .iter()
+            .map(|column| format!(",\n  {column} bigint not null"))
+            .collect::<Vec<_>>()
+            .join("");
+
+        let (flattened, flattened_argument) = self.flattened_definitions(database);
+
+        let format_str = format!(
+            "\ncreate table if not exists {table}_history(\n  entity {{}} not null{columns}{flattened}{tracking},\n  valid_from bigint not null,\n  valid_to bigint\n);"
+        );
+
+        let definitions = self
+            .fields
+            .iter()
+            .map(|field| field.sql_type_definition(sqlx, database));
+
+        let table_argument = self.table_argument(database);
+        let table_expression = self.table_expression();
+
+        quote! {
+            let sql = sql + &format!(
+                #format_str,
+                <EntityId as #sqlx::Type<#database>>::type_info().name(),
+                #(#definitions,)*
+                #table_argument
+                #flattened_argument
+            );
+            let sql = sql + "\n" + &<#database as ::erm::index::CreateIndex>::create_index(
+                &format!("{}_history", #table_expression),
+                &["entity", "valid_from"],
+                false,
+            );
+        }
+    }
+
+    /// Appends the creation of the child table holding the elements of the collection `field`.
+    ///
+    /// Elements are removed along with the component's row, but not when it is soft-deleted.
+    fn collection_table_creator(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        field: &Field,
+    ) -> TokenStream {
+        let table = self.table_placeholder();
+        let column = field.column_name();
+
+        let format_str = format!(
+            "\ncreate table if not exists {table}_{column}(\n  entity {{}} not null references {table}(entity) on delete cascade,\n  position bigint not null,\n  value {{}} {{}},\n  primary key (entity, position)\n);"
+        );
+
+        let element = field.element().cloned().expect("collections are Vecs");
+        let definition =
+            Field::column("value".to_string(), element, false).sql_type_definition(sqlx, database);
+
+        let table_argument = self.table_argument(database);
+
+        quote! {
+            let sql = sql + &format!(
+                #format_str,
+                <EntityId as #sqlx::Type<#database>>::type_info().name(),
+                #definition,
+                #table_argument
+            );
+        }
+    }
+
+    /// Queues the insertion of the elements of each collection, after removing the previous
+    /// elements if `replace` is set.
+    fn write_collections(
+        &self,
+        database: &TokenStream,
+        placeholder_char: char,
+        replace: bool,
+    ) -> TokenStream {
+        let table = self.table_placeholder();
+
+        let writes = self.collections.iter().map(|field| {
+            let column = field.column_name();
+            let member = field.ident();
+
+            let insert = self.statement(
+                database,
+                placeholder_char,
+                &format!("insert {column}"),
+                format!("insert into {table}_{column}(entity, position, value) values({placeholder_char}1, {placeholder_char}2, {placeholder_char}3)"),
+            );
+
+            let delete = if replace {
+                let delete = self.statement(
+                    database,
+                    placeholder_char,
+                    &format!("delete {column}"),
+                    format!("delete from {table}_{column} where entity = {placeholder_char}1"),
+                );
+
+                quote! { query.query(#delete, |query| query); }
+            } else {
+                TokenStream::new()
+            };
+
+            quote! {
+                #delete
+                for (position, value) in self.#member.iter().enumerate() {
+                    query.query(#insert, move |query| query.bind(position as i64).bind(value));
+                }
+            }
+        });
+
+        quote! { #(#writes)* }
+    }
+
+    /// Placeholder for the definitions of the flattened columns within `create table`
+    /// format strings, along with the argument substituting it.
+    fn flattened_definitions(&self, database: &TokenStream) -> (&str, TokenStream) {
+        if self.flattened.is_empty() {
+            return ("", TokenStream::new());
+        }
+
+        let columns = self.flattened_columns(database);
+
+        (
+            "{flattened}",
+            quote! {
+                flattened = #columns.definitions(),
+            },
+        )
+    }
+
+    /// Argument substituting `{table}` in format strings built using [`Self::table_placeholder`].
+    fn table_argument(&self, database: &TokenStream) -> TokenStream {
+        if !self.generic_table() {
+            return TokenStream::new();
+        }
+
+        quote! {
+            table = <Self as ::erm::component::Component<#database>>::table(),
+        }
+    }
+
     fn table(&self) -> TokenStream {
-        let table_name = &self.table_name.trim_matches('"');
+        let table_name = self.table_expression();
         quote! {
             fn table() -> &'static str {
                 #table_name
@@ -151,52 +745,400 @@ impl Component {
             .iter()
             .map(|field| field.column_definition(sqlx, database));
 
+        let tracking = self
+            .tracking_columns()
+            .iter()
+            .map(|column| {
+                quote! {
+                    ::erm::component::ColumnDefinition::<#database> {
+                        name: #column,
+                        type_info: <i64 as #sqlx::Type<#database>>::type_info(),
+                        references: None,
+                        cascade: false,
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if self.flattened.is_empty() {
+            return quote! {
+                fn columns() -> Vec<::erm::component::ColumnDefinition::<#database>> {
+                    vec![#(#columns,)* #(#tracking,)*]
+                }
+            };
+        }
+
+        let flattened = self.flattened_columns(database);
+
         quote! {
             fn columns() -> Vec<::erm::component::ColumnDefinition::<#database>> {
-                vec![#(#columns,)*]
+                let mut columns = vec![#(#columns,)*];
+                columns.extend(#flattened.into_columns());
+                columns.extend([#(#tracking,)*]);
+                columns
             }
         }
     }
 
-    fn remove(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+    /// Names of the columns maintained by the statements themselves, used for
+    /// change tracking and versioning, if enabled.
+    ///
+    /// The `version` column is always last, see `erm::version::Version`.
+    pub fn tracking_columns(&self) -> Vec<&'static str> {
+        let mut columns = Vec::new();
+
+        if self.track_changes {
+            columns.extend(["added_at", "changed_at"]);
+        }
+
+        if self.versioned {
+            columns.push("version");
+        }
+
+        columns
+    }
+
+    /// Queues a statement which depends on the database dialect, and is therefore
+    /// generated by `init` once, on first use.
+    ///
+    /// Statics are shared by all instantiations of a generic impl, so statements of
+    /// generic tables are interned per instantiation under `key` instead.
+    fn lazy_statement(&self, database: &TokenStream, key: &str, init: TokenStream) -> TokenStream {
+        if self.generic_table() {
+            return quote! {
+                query.query(::erm::component::intern::<(Self, #database)>(#key, || #init), |query| query);
+            };
+        }
+
         quote! {
+            {
+                static STATEMENT: ::std::sync::OnceLock<String> = ::std::sync::OnceLock::new();
+
+                query.query(STATEMENT.get_or_init(|| #init), |query| query);
+            }
+        }
+    }
+
+    /// Records the operation in the changelog, if the component opted in using `#[erm(changelog)]`.
+    fn record(&self, database: &TokenStream, operation: TokenStream) -> TokenStream {
+        if !self.changelog {
+            return TokenStream::new();
+        }
+
+        let key = format!("record {operation}");
+
+        self.lazy_statement(
+            database,
+            &key,
+            quote! {
+                ::erm::changelog::record::<#database, Self>(#operation)
+            },
+        )
+    }
+
+    /// Copies the current version into the history table, if the component opted in using `#[erm(history)]`.
+    fn open_version(&self, database: &TokenStream) -> TokenStream {
+        if !self.history {
+            return TokenStream::new();
+        }
+
+        self.lazy_statement(
+            database,
+            "open",
+            quote! {
+                ::erm::history::open::<#database, Self>()
+            },
+        )
+    }
+
+    /// Ends the validity of the current version in the history table, if the component
+    /// opted in using `#[erm(history)]`.
+    fn close_version(&self, database: &TokenStream) -> TokenStream {
+        if !self.history {
+            return TokenStream::new();
+        }
+
+        self.lazy_statement(
+            database,
+            "close",
+            quote! {
+                ::erm::history::close::<#database, Self>()
+            },
+        )
+    }
+
+    /// Implements `Component::remove_component`, which `Removable::remove` delegates to,
+    /// so that despawning an entity removes its components the same way.
+    fn remove(&self, sqlx: &TokenStream, database: &TokenStream) -> (TokenStream, TokenStream) {
+        let record = self.record(database, quote! { ::erm::subscription::Operation::Delete });
+        let close_version = self.close_version(database);
+
+        let remove_component = quote! {
+            fn remove_component<'query, EntityId>(query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
+            {
+                #record
+                #close_version
+                query.query(<Self as Component<#database>>::delete_statement(), |query| query)
+            }
+        };
+
+        let remove = quote! {
             fn remove<'query, EntityId>(query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
             where
                 EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
             {
-                query.query(<Self as Component<#database>>::DELETE, |query| query)
+                <Self as ::erm::component::Component<#database>>::remove_component(query)
             }
-        }
+        };
+
+        (remove_component, remove)
     }
 
-    fn insert(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+    fn insert(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder_char: char,
+    ) -> TokenStream {
+        let record = self.record(database, quote! { ::erm::subscription::Operation::Insert });
+        let open_version = self.open_version(database);
+        let purge = self.purge(database, placeholder_char);
+        let collections = self.write_collections(database, placeholder_char, false);
+        let advance_tick = self.advance_tick();
+
         quote! {
             fn insert<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
             where
                 EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
             {
-                query.query(<Self as Component<#database>>::INSERT, move |query| {
+                #purge
+                #advance_tick
+                query.query(<Self as Component<#database>>::insert_statement(), move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+                #collections
+                #record
+                #open_version
             }
         }
     }
 
-    fn update(&self, database: &TokenStream) -> TokenStream {
+    /// Deletes a soft-deleted row before inserting, if the component opted in using
+    /// `#[erm(soft_delete)]`, since it would otherwise conflict with the new one.
+    fn purge(&self, database: &TokenStream, placeholder_char: char) -> TokenStream {
+        if !self.soft_delete {
+            return TokenStream::new();
+        }
+
+        let table = self.table_placeholder();
+        let purge = self.statement(
+            database,
+            placeholder_char,
+            "purge",
+            format!(
+                "delete from {table} where entity = {placeholder_char}1 and deleted_at is not null"
+            ),
+        );
+
+        quote! {
+            query.query(#purge, |query| query);
+        }
+    }
+
+    fn update(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder_char: char,
+    ) -> TokenStream {
+        let record = self.record(database, quote! { ::erm::subscription::Operation::Update });
+        let close_version = self.close_version(database);
+        let open_version = self.open_version(database);
+        let collections = self.write_collections(database, placeholder_char, true);
+        let advance_tick = self.advance_tick();
+
+        let statement = if self.versioned {
+            quote! { <Self as ::erm::component::Versioned<#database>>::overwrite_statement() }
+        } else {
+            quote! { <Self as Component<#database>>::update_statement() }
+        };
+
         quote! {
             fn update<'query, EntityId>(&'query self, query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
             where
-                EntityId: sqlx::Encode<'query, #database> + sqlx::Type<#database> + Clone + 'query
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
             {
-                query.query(<Self as Component<#database>>::UPDATE, move |query| {
+                #advance_tick
+                query.query(#statement, move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+                #collections
+                #record
+                #close_version
+                #open_version
             }
         }
     }
 
+    /// Implements `SoftDelete`, if the component opted in using `#[erm(soft_delete)]`.
+    ///
+    /// Restoring a component opens a new version in its history, and is recorded in
+    /// the changelog as an insert.
+    fn soft_delete(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder_char: char,
+    ) -> TokenStream {
+        if !self.soft_delete {
+            return TokenStream::new();
+        }
+
+        let component_name = &self.typename;
+        let (impl_generics, ty_generics, where_clause) = self.split_generics(sqlx, database);
+        let table = self.table_placeholder();
+        let restore = self.statement_items(
+            database,
+            placeholder_char,
+            "RESTORE",
+            format!("update {table} set deleted_at = null where entity = {placeholder_char}1 and deleted_at is not null"),
+        );
+        let record = self.record(database, quote! { ::erm::subscription::Operation::Insert });
+        let open_version = self.open_version(database);
+
+        quote! {
+            impl #impl_generics ::erm::component::SoftDelete<#database> for #component_name #ty_generics #where_clause {
+                #restore
+
+                fn restore_component<'query, EntityId>(query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+                where
+                    EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
+                {
+                    query.query(<Self as ::erm::component::SoftDelete<#database>>::restore_statement(), |query| query);
+                    #record
+                    #open_version
+                }
+            }
+        }
+    }
+
+    /// Implements `Versioned`, if the component opted in using `#[erm(versioned)]`.
+    fn versioned(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder_char: char,
+    ) -> TokenStream {
+        if !self.versioned {
+            return TokenStream::new();
+        }
+
+        let component_name = &self.typename;
+        let (impl_generics, ty_generics, where_clause) = self.split_generics(sqlx, database);
+        let overwrite = self.statement_items(
+            database,
+            placeholder_char,
+            "OVERWRITE",
+            self.update_statement(placeholder_char, false),
+        );
+
+        let record = self.record(database, quote! { ::erm::subscription::Operation::Update });
+        let close_version = self.close_version(database);
+        let open_version = self.open_version(database);
+        let collections = self.write_collections(database, placeholder_char, true);
+        let advance_tick = self.advance_tick();
+
+        quote! {
+            impl #impl_generics ::erm::component::Versioned<#database> for #component_name #ty_generics #where_clause {
+                #overwrite
+
+                fn update_version<'query, EntityId>(
+                    &'query self,
+                    version: i64,
+                    query: &mut ::erm::entity::EntityPrefixedQuery<'query, #database, EntityId>,
+                ) where
+                    EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
+                {
+                    #advance_tick
+                    query.guarded_query(<Self as Component<#database>>::update_statement(), move |query| {
+                        <Self as Serializable<#database>>::serialize(self, query).bind(version)
+                    });
+                    #collections
+                    #record
+                    #close_version
+                    #open_version
+                }
+            }
+        }
+    }
+
+    /// Delegates the [`Serializable`] and [`Removable`] hooks to the component's own
+    /// `Hooks` implementation, if the component opted in using `#[erm(hooks)]`.
+    fn hooks(&self, sqlx: &TokenStream, database: &TokenStream) -> (TokenStream, TokenStream) {
+        if !self.hooks {
+            return (TokenStream::new(), TokenStream::new());
+        }
+
+        let insert_hooks = quote! {
+            fn on_insert<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                <Self as ::erm::component::Hooks<#database>>::on_insert(self, entity, connection)
+            }
+
+            fn on_update<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                <Self as ::erm::component::Hooks<#database>>::on_update(self, entity, connection)
+            }
+        };
+
+        let remove_hooks = quote! {
+            fn on_remove<'hook, EntityId>(
+                entity: EntityId,
+                connection: &'hook mut <#database as #sqlx::Database>::Connection,
+            ) -> impl ::core::future::Future<Output = Result<(), #sqlx::Error>> + Send + 'hook
+            where
+                EntityId: for<'e> #sqlx::Encode<'e, #database> + #sqlx::Type<#database> + Clone + Send + 'hook,
+            {
+                <Self as ::erm::component::Hooks<#database>>::on_remove(entity, connection)
+            }
+        };
+
+        (insert_hooks, remove_hooks)
+    }
+
     fn field_serializer(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
-        let binds = self.fields.iter().map(Field::serialize);
+        let binds = match &self.variants {
+            Some(variants) => vec![crate::variant::serialize(variants)],
+            None => self
+                .fields
+                .iter()
+                .map(Field::serialize)
+                .chain(self.flattened.iter().map(|field| {
+                    let typename = field.typename();
+                    let name = field.ident();
+
+                    quote! {
+                        let query = <#typename as ::erm::flatten::Flatten<#database>>::serialize(&self.#name, query);
+                    }
+                }))
+                .collect(),
+        };
 
         quote! {
             fn serialize<'q>(
@@ -212,11 +1154,22 @@ impl Component {
 
     fn field_deserializer(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let component_name = &self.typename;
-        let deserialized_fields = self.fields.iter().map(Field::deserialize);
+        let deserialized_fields = self
+            .fields
+            .iter()
+            .map(|field| field.deserialize(sqlx, component_name))
+            .chain(self.flattened.iter().map(|field| {
+                let typename = field.typename();
+                let name = field.field_name();
+
+                quote! {
+                    let #name = <#typename as ::erm::flatten::Flatten<#database>>::deserialize(row)
+                }
+            }));
 
         let columns = self.fields.iter().map(|field| match field {
             Field::Numbered { ident, .. } => {
-                let ident = format!("column{}", ident.to_string());
+                let ident = format!("column{}", ident);
                 quote! {
                     #ident
                 }
@@ -229,53 +1182,146 @@ impl Component {
             }
         });
 
-        let assignments = self.fields.iter().map(|field| match field {
-            Field::Numbered { ident, .. } => {
-                let ident = Ident::new(&format!("self_{ident}"), ident.span());
+        let assignments = self
+            .fields
+            .iter()
+            .chain(&self.flattened)
+            .chain(&self.collections)
+            .chain(&self.skipped)
+            .map(Field::assignment);
 
-                quote! {
-                    #ident?
-                }
+        // Collections are aggregated into JSON arrays, read after the flattened columns.
+        let deserialized_collections = self.collections.iter().map(|field| {
+            let name = field.field_name();
+            let typename = field.typename();
+
+            quote! {
+                let #name = row.try_get::<::erm::json::Json<#typename>>().map(|field| field.0);
             }
-            Field::Named { ident, .. } => quote! {
-                #ident: #ident?
-            },
         });
 
-        let constructor = match self
-            .fields
-            .first()
-            .map(|field| matches!(field, Field::Named { .. }))
-        {
-            None => quote! { #component_name },
-            Some(true) => quote! {
-                #component_name {
-                    #(#assignments,)*
-                }
-            },
-            Some(false) => quote! {
-                #component_name(#(#assignments,)*)
-            },
+        // Braced syntax also constructs tuple and unit structs, regardless of skipped fields.
+        let constructor = quote! {
+            #component_name {
+                #(#assignments,)*
+            }
         };
 
-        let table_name = &self.table_name;
+        let table_name = if self.generic_table() {
+            quote! { <Self as ::erm::component::Component<#database>>::table() }
+        } else {
+            let table_name = &self.table_name;
+            quote! { #table_name }
+        };
 
-        quote! {
-            fn cte() -> Box<dyn ::erm::cte::CommonTableExpression> {
-                Box::new(::erm::cte::Extract {
-                    table: #table_name,
-                    columns: &[
-                        #(#columns,)*
-                    ],
-                })
-            }
+        let tracking = self.tracking_columns();
+        let skip_tracking = tracking.len();
 
-            fn deserialize(row: &mut ::erm::row::OffsetRow<<#database as #sqlx::Database>::Row>) -> Result<Self, #sqlx::Error> {
+        let deserialize = match &self.variants {
+            Some(variants) => crate::variant::deserialize(variants, sqlx, skip_tracking),
+            None => quote! {
                 #(#deserialized_fields;)*
 
+                #(#deserialized_collections)*
+
+                row.skip(#skip_tracking);
+
                 let component = #constructor;
 
                 Ok(component)
+            },
+        };
+
+        let history = self.history;
+        let soft_delete = self.soft_delete;
+
+        // Collections are aggregated ahead of the tracking columns, which are then selected
+        // as computed columns themselves, so that `version` remains the last column.
+        let (static_tracking, computed_tracking) = if self.collections.is_empty() {
+            (tracking.clone(), Vec::new())
+        } else {
+            (Vec::new(), tracking.clone())
+        };
+
+        // Flattened columns are only known at runtime, and are therefore collected once.
+        let columns = if self.flattened.is_empty() {
+            quote! {
+                &[
+                    #(#columns,)*
+                    #(#static_tracking,)*
+                ]
+            }
+        } else {
+            let flattened = self.flattened_columns(database);
+
+            quote! {
+                {
+                    static COLUMNS: ::std::sync::OnceLock<Vec<&'static str>> = ::std::sync::OnceLock::new();
+
+                    COLUMNS.get_or_init(|| {
+                        let flattened = #flattened;
+
+                        [#(#columns,)*]
+                            .into_iter()
+                            .chain(flattened.names())
+                            .chain([#(#static_tracking,)*])
+                            .collect()
+                    })
+                }
+            }
+        };
+
+        let computed = if self.collections.is_empty() {
+            quote! { &[] }
+        } else {
+            let aggregates = self.collections.iter().map(|field| {
+                let column = field.column_name();
+                let table = format!("{}_{column}", self.table_name);
+                let parent = &self.table_name;
+
+                quote! {
+                    (#column, <#database as ::erm::collection::Collection>::aggregate(#table, #parent))
+                }
+            });
+
+            quote! {
+                {
+                    static COMPUTED: ::std::sync::OnceLock<Vec<(&'static str, String)>> = ::std::sync::OnceLock::new();
+
+                    COMPUTED.get_or_init(|| vec![
+                        #(#aggregates,)*
+                        #((#computed_tracking, #computed_tracking.to_string()),)*
+                    ])
+                }
+            }
+        };
+
+        let collections = self
+            .collections
+            .iter()
+            .map(|field| format!("{}_{}", self.table_name, field.column_name()));
+
+        let extract = quote! {
+            ::erm::cte::Extract {
+                table: #table_name,
+                columns: #columns,
+                computed: #computed,
+                collections: &[#(#collections,)*],
+                history: #history,
+                soft_delete: #soft_delete,
+                as_of: None,
+                include_deleted: false,
+                lock: None,
+            }
+        };
+
+        quote! {
+            fn cte() -> Box<dyn ::erm::cte::CommonTableExpression> {
+                Box::new(#extract)
+            }
+
+            fn deserialize(row: &mut ::erm::row::OffsetRow<<#database as #sqlx::Database>::Row>) -> Result<Self, #sqlx::Error> {
+                #deserialize
             }
         }
     }
@@ -285,12 +1331,41 @@ impl Parse for Component {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let derive = DeriveInput::parse(input)?;
 
-        let Data::Struct(data) = derive.data else {
-            return Err(Error::new(
-                derive.span(),
-                "Component can only be derived for struct types",
-            ));
-        };
+        let (fields, skipped, flattened, collections, variants) =
+            match derive.data {
+                Data::Struct(data) => {
+                    let (skipped, fields): (Vec<_>, Vec<_>) = Result::<Vec<Field>, _>::from_iter(
+                        data.fields.into_iter().enumerate().map(Field::try_from),
+                    )?
+                    .into_iter()
+                    .partition(Field::skip);
+
+                    let (flattened, fields): (Vec<_>, Vec<_>) =
+                        fields.into_iter().partition(Field::flatten);
+
+                    let (collections, fields) = fields.into_iter().partition(Field::collection);
+
+                    (fields, skipped, flattened, collections, None)
+                }
+                Data::Enum(data) if !data.variants.is_empty() => {
+                    let variants = Result::<Vec<Variant>, _>::from_iter(
+                        data.variants.into_iter().map(Variant::try_from),
+                    )?;
+                    crate::variant::check_columns(&variants)?;
+
+                    (
+                        crate::variant::columns(&variants),
+                        Vec::new(),
+                        Vec::new(),
+                        Vec::new(),
+                        Some(variants),
+                    )
+                }
+                _ => return Err(Error::new(
+                    derive.ident.span(),
+                    "Component can only be derived for structs and enums with at least one variant",
+                )),
+            };
 
         let attributes: Vec<_> = Result::<Vec<Vec<_>>, syn::Error>::from_iter(
             derive
@@ -307,21 +1382,142 @@ impl Parse for Component {
         .flatten()
         .collect();
 
-        let table_name = attributes
+        // Generic components get a table per instantiation by default.
+        let parameters: Vec<_> = derive
+            .generics
+            .params
             .iter()
-            .find_map(ComponentAttribute::table)
-            .unwrap_or(derive.ident.to_string());
+            .filter_map(|parameter| match parameter {
+                GenericParam::Type(parameter) => Some(parameter.ident.to_string()),
+                GenericParam::Const(parameter) => Some(parameter.ident.to_string()),
+                GenericParam::Lifetime(_) => None,
+            })
+            .collect();
 
-        let type_name = derive.ident.clone();
+        // Columns of reflected flattened fields and collections are named by constants.
+        if let (Some(field), false) = (flattened.first(), parameters.is_empty()) {
+            return Err(Error::new(
+                field.typename().span(),
+                "flatten is not supported on generic components",
+            ));
+        }
+
+        if let (Some(field), false) = (collections.first(), parameters.is_empty()) {
+            return Err(Error::new(
+                field.typename().span(),
+                "collection is not supported on generic components",
+            ));
+        }
 
-        let fields = Result::<Vec<Field>, _>::from_iter(
-            data.fields.into_iter().enumerate().map(Field::try_from),
-        )?;
+        let table_name = match attributes.iter().find_map(ComponentAttribute::table) {
+            Some((table_name, span)) => {
+                for placeholder in table_name.split('{').skip(1) {
+                    let parameter = placeholder.split('}').next().unwrap_or_default();
+
+                    if !parameters.iter().any(|name| name == parameter) {
+                        return Err(Error::new(
+                            span,
+                            format!("table name refers to unknown type parameter `{parameter}`"),
+                        ));
+                    }
+                }
+
+                table_name
+            }
+            None => std::iter::once(derive.ident.to_string())
+                .chain(
+                    parameters
+                        .iter()
+                        .map(|parameter| format!("{{{parameter}}}")),
+                )
+                .collect::<Vec<_>>()
+                .join("_"),
+        };
+
+        let track_changes = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::TrackChanges));
+
+        let hooks = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::Hooks));
+
+        let changelog = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::Changelog));
+
+        let history = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::History));
+
+        let soft_delete = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::SoftDelete));
+
+        let versioned = attributes
+            .iter()
+            .any(|attribute| matches!(attribute, ComponentAttribute::Versioned));
+
+        // Neither keeps past elements of collections, which would be read as the current ones.
+        if let (Some(field), true) = (collections.first(), history || changelog) {
+            return Err(Error::new(
+                field.typename().span(),
+                "collection is not supported on components with history or changelog",
+            ));
+        }
+
+        let mut indexes: Vec<_> = fields
+            .iter()
+            .filter_map(|field| {
+                field.index().map(|unique| Index {
+                    columns: vec![field.column_name().to_string()],
+                    unique,
+                })
+            })
+            .collect();
+
+        for (names, unique) in attributes.iter().filter_map(ComponentAttribute::index) {
+            if variants.is_some() {
+                return Err(Error::new(
+                    derive.ident.span(),
+                    "index and unique are not supported on enum components",
+                ));
+            }
+
+            let columns = Result::<Vec<_>, _>::from_iter(names.iter().map(|name| {
+                fields
+                    .iter()
+                    .find(|field| *name == field.ident().to_string())
+                    .map(|field| field.column_name().to_string())
+                    .ok_or_else(|| {
+                        Error::new(
+                            name.span(),
+                            format!("index refers to unknown field `{name}`, or to a field without a column of its own"),
+                        )
+                    })
+            }))?;
+
+            indexes.push(Index { columns, unique });
+        }
+
+        let type_name = derive.ident.clone();
 
         Ok(Component {
             typename: type_name,
+            generics: derive.generics,
             table_name,
             fields,
+            skipped,
+            flattened,
+            collections,
+            variants,
+            track_changes,
+            hooks,
+            changelog,
+            history,
+            soft_delete,
+            versioned,
+            indexes,
         })
     }
 }
@@ -349,13 +1545,36 @@ impl Parse for ComponentAttributeList {
 pub enum ComponentAttribute {
     /// Changes the name of the Component's sql table.
     Table { name: Literal },
+    /// Maintains `added_at` and `changed_at` ticks for each row.
+    TrackChanges,
+    /// Runs the component's `Hooks` implementation on insert, update and remove.
+    Hooks,
+    /// Records every insert, update and remove in the `erm_changelog` table.
+    Changelog,
+    /// Keeps every version of the component in a `<table>_history` table.
+    History,
+    /// Marks removed rows using a `deleted_at` column, instead of deleting them.
+    SoftDelete,
+    /// Maintains a `version` column, and only applies versioned updates to the current version.
+    Versioned,
+    /// Creates an index spanning the columns of several fields, which is unique if `unique` is set.
+    Index { fields: Vec<Ident>, unique: bool },
 }
 
 impl ComponentAttribute {
-    pub fn table(&self) -> Option<String> {
-        #[allow(irrefutable_let_patterns)]
+    /// Table name, without quotes, along with the span of the literal.
+    pub fn table(&self) -> Option<(String, proc_macro2::Span)> {
         if let ComponentAttribute::Table { name } = self {
-            Some(name.to_string())
+            Some((name.to_string().trim_matches('"').to_string(), name.span()))
+        } else {
+            None
+        }
+    }
+
+    /// Fields of a composite index, and whether it is unique.
+    pub fn index(&self) -> Option<(&[Ident], bool)> {
+        if let ComponentAttribute::Index { fields, unique } = self {
+            Some((fields, *unique))
         } else {
             None
         }
@@ -374,10 +1593,49 @@ impl Parse for ComponentAttribute {
                     name: input.parse()?,
                 }
             }
+            "track_changes" => ComponentAttribute::TrackChanges,
+            "hooks" => ComponentAttribute::Hooks,
+            "changelog" => ComponentAttribute::Changelog,
+            "history" => ComponentAttribute::History,
+            "soft_delete" => ComponentAttribute::SoftDelete,
+            "versioned" => ComponentAttribute::Versioned,
+            "index" | "unique" => {
+                let content;
+                syn::parenthesized!(content in input);
+
+                let key: Ident = content.parse()?;
+                if key != "fields" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown {ident} option `{key}`, expected `fields`"),
+                    ));
+                }
+                content.parse::<Token![=]>()?;
+
+                let list;
+                syn::bracketed!(list in content);
+
+                let fields: Vec<Ident> = list
+                    .parse_terminated(Ident::parse, Token![,])?
+                    .into_iter()
+                    .collect();
+
+                if fields.is_empty() {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!("{ident} requires at least one field"),
+                    ));
+                }
+
+                ComponentAttribute::Index {
+                    fields,
+                    unique: ident == "unique",
+                }
+            }
             _ => {
                 return Err(syn::Error::new(
                     ident.span(),
-                    "unexpected Component attribute",
+                    format!("unknown Component attribute `{ident}`, expected one of `table`, `track_changes`, `hooks`, `changelog`, `history`, `soft_delete`, `versioned`, `index` or `unique`"),
                 ))
             }
         })
@@ -1,34 +1,60 @@
 use proc_macro2::{Ident, Literal, Punct, TokenStream};
-use quote::quote;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::Token;
-use syn::{parse::Parse, Type};
+use syn::{parse::Parse, Expr, LitStr, Type};
 
 pub enum Field {
     Numbered {
         ident: TokenStream,
         typename: Type,
         intermediate_type: Option<Type>,
+        /// Whether values are converted from the intermediate type using `TryFrom`.
+        fallible: bool,
+        /// Whether the field is stored as a JSON document, within `erm::json::Json`.
+        json: bool,
+        references: Option<Type>,
+        cascade: bool,
         column_name: String,
+        nullable: bool,
+        default: Option<Expr>,
+        skip: bool,
+        /// Whether the field's type derives `Flatten`, and is stored as columns of its own.
+        flatten: bool,
+        /// Whether the field is a `Vec`, whose elements are stored in a child table.
+        collection: bool,
+        /// Whether an index is created on the column, which is unique if `Some(true)`.
+        index: Option<bool>,
     },
     Named {
         ident: TokenStream,
         typename: Type,
         intermediate_type: Option<Type>,
+        fallible: bool,
+        json: bool,
+        references: Option<Type>,
+        cascade: bool,
         column_name: String,
+        nullable: bool,
+        default: Option<Expr>,
+        skip: bool,
+        flatten: bool,
+        collection: bool,
+        index: Option<bool>,
     },
 }
 
 impl Field {
     pub fn ident(&self) -> &TokenStream {
         match self {
-            Field::Numbered { ident, .. } | Field::Named { ident, .. } => &ident,
+            Field::Numbered { ident, .. } | Field::Named { ident, .. } => ident,
         }
     }
 
     pub fn column_name(&self) -> &str {
         match self {
-            Field::Numbered { column_name, .. } | Field::Named { column_name, .. } => &column_name,
+            Field::Numbered { column_name, .. } | Field::Named { column_name, .. } => column_name,
         }
     }
 
@@ -44,7 +70,7 @@ impl Field {
 
     pub fn typename(&self) -> &Type {
         match self {
-            Field::Numbered { typename, .. } | Field::Named { typename, .. } => &typename,
+            Field::Numbered { typename, .. } | Field::Named { typename, .. } => typename,
         }
     }
 
@@ -59,29 +85,171 @@ impl Field {
         }
     }
 
+    /// Whether the field is converted from its intermediate type using `TryFrom`.
+    pub fn fallible(&self) -> bool {
+        match self {
+            Field::Numbered { fallible, .. } | Field::Named { fallible, .. } => *fallible,
+        }
+    }
+
+    /// Whether the field is stored as JSON, using `erm::json::Json` as its intermediate type.
+    pub fn json(&self) -> bool {
+        match self {
+            Field::Numbered { json, .. } | Field::Named { json, .. } => *json,
+        }
+    }
+
+    /// Component whose table this field holds a foreign key into, if any.
+    pub fn references(&self) -> Option<&Type> {
+        match self {
+            Field::Numbered { references, .. } | Field::Named { references, .. } => {
+                references.as_ref()
+            }
+        }
+    }
+
     pub fn column_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let name = self.column_name();
-        let typename = self.typename();
+        let typename = self.intermediate().unwrap_or(self.typename());
+        let cascade = self.cascade();
 
-        if let Some(intermediate) = self.intermediate() {
+        let references = if let Some(referenced) = self.references() {
             quote! {
-                ::erm::component::ColumnDefinition::<#database> {
-                    name: #name,
-                    type_info: <#intermediate as #sqlx::Type<#database>>::type_info(),
-                }
+                Some(<#referenced as ::erm::component::Component<#database>>::table())
             }
         } else {
-            quote! {
-                ::erm::component::ColumnDefinition::<#database> {
-                    name: #name,
-                    type_info: <#typename as #sqlx::Type<#database>>::type_info(),
-                }
+            quote! { None }
+        };
+
+        quote! {
+            ::erm::component::ColumnDefinition::<#database> {
+                name: #name,
+                type_info: <#typename as #sqlx::Type<#database>>::type_info(),
+                references: #references,
+                cascade: #cascade,
             }
         }
     }
 
+    /// Column holding a value of `typename`, which is not a field of its own, such as
+    /// the columns of enum variants.
+    pub fn column(column_name: String, typename: Type, nullable: bool) -> Self {
+        Field::Named {
+            ident: Ident::new(&column_name, proc_macro2::Span::call_site()).into_token_stream(),
+            typename,
+            intermediate_type: None,
+            fallible: false,
+            json: false,
+            references: None,
+            cascade: false,
+            column_name,
+            nullable,
+            default: None,
+            skip: false,
+            flatten: false,
+            collection: false,
+            index: None,
+        }
+    }
+
+    /// Expression used in place of null values of the column, if any.
+    pub fn default(&self) -> Option<&Expr> {
+        match self {
+            Field::Numbered { default, .. } | Field::Named { default, .. } => default.as_ref(),
+        }
+    }
+
+    /// Whether the field is kept in memory only, and set using `Default` when loaded.
+    pub fn skip(&self) -> bool {
+        match self {
+            Field::Numbered { skip, .. } | Field::Named { skip, .. } => *skip,
+        }
+    }
+
+    /// Whether the field is stored in the columns of its type, prefixed by the field's name.
+    pub fn flatten(&self) -> bool {
+        match self {
+            Field::Numbered { flatten, .. } | Field::Named { flatten, .. } => *flatten,
+        }
+    }
+
+    /// Whether the elements of the field are stored in a `<table>_<column>` child table.
+    pub fn collection(&self) -> bool {
+        match self {
+            Field::Numbered { collection, .. } | Field::Named { collection, .. } => *collection,
+        }
+    }
+
+    /// Whether an index is created on the column, and whether it is unique.
+    pub fn index(&self) -> Option<bool> {
+        match self {
+            Field::Numbered { index, .. } | Field::Named { index, .. } => *index,
+        }
+    }
+
+    /// Type of the elements of collection fields.
+    pub fn element(&self) -> Option<&Type> {
+        element(self.typename())
+    }
+
+    /// Prefix of the columns of flattened fields, such as `pos_`.
+    pub fn prefix(&self) -> String {
+        format!("{}_", self.column_name())
+    }
+
+    /// Initializes the field when constructing the struct, either from its column read by
+    /// [`Self::deserialize`], or using `Default` for skipped fields.
+    pub fn assignment(&self) -> TokenStream {
+        let member = self.ident();
+
+        if self.skip() {
+            return quote! {
+                #member: ::core::default::Default::default()
+            };
+        }
+
+        let name = self.field_name();
+
+        quote! {
+            #member: #name?
+        }
+    }
+
+    /// Whether the column is nullable regardless of its type.
+    pub fn nullable(&self) -> bool {
+        match self {
+            Field::Numbered { nullable, .. } | Field::Named { nullable, .. } => *nullable,
+        }
+    }
+
+    /// Whether removing the referenced component should also remove this one.
+    pub fn cascade(&self) -> bool {
+        match self {
+            Field::Numbered { cascade, .. } | Field::Named { cascade, .. } => *cascade,
+        }
+    }
+
     pub fn sql_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
-        if let Some(intermediate) = self.intermediate() {
+        let definition = self.sql_type_definition(sqlx, database);
+
+        if let Some(referenced) = self.references() {
+            quote! {
+                #definition,
+                <#referenced as ::erm::component::Component<#database>>::table()
+            }
+        } else {
+            definition
+        }
+    }
+
+    pub fn sql_type_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        if self.nullable() {
+            let typename = self.intermediate().unwrap_or(self.typename());
+            quote! {
+                <#typename as #sqlx::Type<#database>>::type_info().name(),
+                "null"
+            }
+        } else if let Some(intermediate) = self.intermediate() {
             quote! {
                 <#intermediate as #sqlx::Type<#database>>::type_info().name(),
                 if <#intermediate as #sqlx::Type<#database>>::type_info().is_null() {
@@ -107,7 +275,11 @@ impl Field {
         let name = self.ident();
         let typename = self.typename();
 
-        if let Some(intermediate) = self.intermediate() {
+        if self.json() {
+            quote! {
+                let query = query.bind(::erm::json::Json(&self.#name));
+            }
+        } else if let Some(intermediate) = self.intermediate() {
             quote! {
                 let query = query.bind(<&#typename as Into<#intermediate>>::into(&self.#name));
             }
@@ -118,42 +290,51 @@ impl Field {
         }
     }
 
-    pub fn deserialize(&self) -> TokenStream {
-        match self {
-            Field::Numbered {
-                ident,
-                typename,
-                intermediate_type,
-                ..
-            } => {
-                let ident = Ident::new(&format!("self_{ident}"), ident.span());
-
-                if let Some(intermediate) = &intermediate_type {
-                    quote! {
-                        let #ident: Result<#typename, _> = row.try_get::<#intermediate>().map(|field| <#typename as From<#intermediate>>::from(field));
-                    }
-                } else {
-                    quote! {
-                        let #ident = row.try_get::<#typename>();
-                    }
+    /// Reads the column of the field, which belongs to `component`.
+    ///
+    /// Fallible conversions report errors as [`sqlx::Error::Decode`], naming the column.
+    pub fn deserialize(&self, sqlx: &TokenStream, component: &Ident) -> TokenStream {
+        let name = self.field_name();
+        let typename = self.typename();
+
+        let convert = self.intermediate().map(|intermediate| {
+            if self.json() {
+                quote! {
+                    (|field: #intermediate| Ok::<_, #sqlx::Error>(field.0))
                 }
-            }
-            Field::Named {
-                ident,
-                typename,
-                intermediate_type,
-                ..
-            } => {
-                if let Some(intermediate) = &intermediate_type {
-                    quote! {
-                        let #ident: Result<#typename, _> = row.try_get::<#intermediate>().map(|field| <#typename as From<#intermediate>>::from(field));
-                    }
-                } else {
-                    quote! {
-                        let #ident = row.try_get::<#typename>();
-                    }
+            } else if self.fallible() {
+                let column = format!("{component}.{}", self.column_name());
+
+                quote! {
+                    (|field| <#typename as TryFrom<#intermediate>>::try_from(field).map_err(|error| {
+                        #sqlx::Error::Decode(format!("invalid value for {}: {}", #column, error).into())
+                    }))
+                }
+            } else {
+                quote! {
+                    (|field| Ok::<_, #sqlx::Error>(<#typename as From<#intermediate>>::from(field)))
                 }
             }
+        });
+
+        match (self.intermediate(), self.default()) {
+            (Some(intermediate), Some(default)) => quote! {
+                let #name: Result<#typename, #sqlx::Error> = row
+                    .try_get::<Option<#intermediate>>()
+                    .and_then(|field| field.map(#convert).transpose())
+                    .map(|field| field.unwrap_or_else(|| #default));
+            },
+            (Some(intermediate), None) => quote! {
+                let #name: Result<#typename, #sqlx::Error> = row.try_get::<#intermediate>().and_then(#convert);
+            },
+            (None, Some(default)) => quote! {
+                let #name: Result<#typename, _> = row
+                    .try_get::<Option<#typename>>()
+                    .map(|field| field.unwrap_or_else(|| #default));
+            },
+            (None, None) => quote! {
+                let #name = row.try_get::<#typename>();
+            },
         }
     }
 
@@ -194,7 +375,54 @@ impl TryFrom<(usize, syn::Field)> for Field {
 
         let typename = field.ty.clone();
 
-        let intermediate_type = attributes.iter().find_map(FieldAttribute::intermediate);
+        let mut intermediate_type = attributes.iter().find_map(FieldAttribute::intermediate);
+
+        let fallible = attributes.iter().any(FieldAttribute::is_try);
+
+        if fallible && intermediate_type.is_none() {
+            return Err(syn::Error::new_spanned(
+                &field,
+                "try can only be used together with intermediate",
+            ));
+        }
+
+        let json = attributes.iter().any(FieldAttribute::is_json);
+
+        if json {
+            if intermediate_type.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &field,
+                    "json cannot be combined with intermediate",
+                ));
+            }
+
+            if !cfg!(feature = "json") {
+                return Err(syn::Error::new_spanned(
+                    &field,
+                    "json fields require the `json` feature of erm",
+                ));
+            }
+
+            intermediate_type = Some(syn::parse_quote! { ::erm::json::Json<#typename> });
+        }
+
+        let references = attributes.iter().find_map(FieldAttribute::references);
+
+        if references.is_some() && !is_entity_ref(&typename) {
+            return Err(syn::Error::new(
+                typename.span(),
+                "references can only be used on EntityRef fields",
+            ));
+        }
+
+        let cascade = attributes.iter().any(FieldAttribute::is_cascade);
+
+        if cascade && references.is_none() {
+            return Err(syn::Error::new_spanned(
+                &field,
+                "cascade can only be used together with references",
+            ));
+        }
 
         let column_name = attributes
             .iter()
@@ -202,12 +430,82 @@ impl TryFrom<(usize, syn::Field)> for Field {
             .or_else(|| field.ident.as_ref().map(ToString::to_string))
             .unwrap_or_else(|| format!("column{index}"));
 
+        let default = attributes.iter().find_map(FieldAttribute::default);
+
+        let skip = attributes.iter().any(FieldAttribute::is_skip);
+
+        if skip && attributes.len() > 1 {
+            return Err(syn::Error::new_spanned(
+                &field,
+                "skip cannot be combined with other attributes, since the field has no column",
+            ));
+        }
+
+        let flatten = attributes.iter().any(FieldAttribute::is_flatten);
+
+        if flatten && attributes.len() > 1 {
+            return Err(syn::Error::new_spanned(
+                &field,
+                "flatten cannot be combined with other attributes, since the field has no column of its own",
+            ));
+        }
+
+        let collection = attributes.iter().any(FieldAttribute::is_collection);
+
+        if collection {
+            if attributes.len() > 1 {
+                return Err(syn::Error::new_spanned(
+                    &field,
+                    "collection cannot be combined with other attributes, since the field has no column of its own",
+                ));
+            }
+
+            if element(&typename).is_none() {
+                return Err(syn::Error::new(
+                    typename.span(),
+                    "collection can only be used on Vec fields",
+                ));
+            }
+
+            if !cfg!(feature = "json") {
+                return Err(syn::Error::new_spanned(
+                    &field,
+                    "collection fields require the `json` feature of erm",
+                ));
+            }
+        }
+
+        let unique = attributes.iter().any(FieldAttribute::is_unique);
+
+        let indexed = match (attributes.iter().any(FieldAttribute::is_index), unique) {
+            (true, true) => return Err(syn::Error::new_spanned(
+                &field,
+                "index cannot be combined with unique, since unique columns are indexed already",
+            )),
+            (true, false) => Some(false),
+            (false, true) => Some(true),
+            (false, false) => None,
+        };
+
+        // Older rows may hold null, if the column was added later on.
+        let nullable = default.is_some();
+
         if let Some(ident) = field.ident {
             Ok(Field::Named {
                 ident: quote! { #ident },
                 typename,
                 intermediate_type,
+                fallible,
+                json,
+                references,
+                cascade,
                 column_name,
+                nullable,
+                default,
+                skip,
+                flatten,
+                collection,
+                index: indexed,
             })
         } else {
             let index = syn::Index::from(index);
@@ -215,7 +513,17 @@ impl TryFrom<(usize, syn::Field)> for Field {
                 ident: quote! { #index },
                 typename,
                 intermediate_type,
+                fallible,
+                json,
+                references,
+                cascade,
                 column_name,
+                nullable,
+                default,
+                skip,
+                flatten,
+                collection,
+                index: indexed,
             })
         }
     }
@@ -246,6 +554,28 @@ pub enum FieldAttribute {
     Column { name: Literal },
     /// Intermediate type to convert to/from before storing in database.
     Intermediate { typename: Type },
+    /// Convert from the intermediate type using `TryFrom`, failing to decode invalid values.
+    ///
+    /// Values are still stored by converting a reference to the field using `Into`.
+    Try,
+    /// Component table which an EntityRef field holds a foreign key into.
+    References { component: Type },
+    /// Remove this component when the referenced component is removed.
+    Cascade,
+    /// Expression used when the column is null, such as in rows predating the field.
+    Default { value: Expr },
+    /// Keep the field in memory only, setting it using `Default` when loaded.
+    Skip,
+    /// Store the field as a JSON document, using its `Serialize` and `Deserialize` impls.
+    Json,
+    /// Store the fields of the field's type as columns, prefixed by the field's name.
+    Flatten,
+    /// Store the elements of a `Vec` field as rows of a child table.
+    Collection,
+    /// Create an index on the column.
+    Index,
+    /// Create a unique index on the column.
+    Unique,
 }
 
 impl FieldAttribute {
@@ -264,11 +594,60 @@ impl FieldAttribute {
             None
         }
     }
+
+    pub fn references(&self) -> Option<Type> {
+        if let FieldAttribute::References { component } = self {
+            Some(component.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_cascade(&self) -> bool {
+        matches!(self, FieldAttribute::Cascade)
+    }
+
+    pub fn default(&self) -> Option<Expr> {
+        if let FieldAttribute::Default { value } = self {
+            Some(value.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_try(&self) -> bool {
+        matches!(self, FieldAttribute::Try)
+    }
+
+    pub fn is_skip(&self) -> bool {
+        matches!(self, FieldAttribute::Skip)
+    }
+
+    pub fn is_json(&self) -> bool {
+        matches!(self, FieldAttribute::Json)
+    }
+
+    pub fn is_flatten(&self) -> bool {
+        matches!(self, FieldAttribute::Flatten)
+    }
+
+    pub fn is_collection(&self) -> bool {
+        matches!(self, FieldAttribute::Collection)
+    }
+
+    pub fn is_index(&self) -> bool {
+        matches!(self, FieldAttribute::Index)
+    }
+
+    pub fn is_unique(&self) -> bool {
+        matches!(self, FieldAttribute::Unique)
+    }
 }
 
 impl Parse for FieldAttribute {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let ident: Ident = input.parse()?;
+        // `try` is a keyword, so identifiers are parsed including keywords.
+        let ident = input.call(Ident::parse_any)?;
 
         Ok(match ident.to_string().as_str() {
             "column" => {
@@ -285,7 +664,78 @@ impl Parse for FieldAttribute {
                     typename: input.parse()?,
                 }
             }
-            _ => return Err(syn::Error::new(ident.span(), "unexpected Field attribute")),
+            "references" => {
+                input.parse::<Token![=]>()?;
+
+                FieldAttribute::References {
+                    component: input.parse()?,
+                }
+            }
+            "cascade" => FieldAttribute::Cascade,
+            "default" => {
+                input.parse::<Token![=]>()?;
+
+                FieldAttribute::Default {
+                    value: input.parse::<LitStr>()?.parse()?,
+                }
+            }
+            "try" => FieldAttribute::Try,
+            "skip" => FieldAttribute::Skip,
+            "json" => FieldAttribute::Json,
+            "flatten" => FieldAttribute::Flatten,
+            "collection" => FieldAttribute::Collection,
+            "index" => FieldAttribute::Index,
+            "unique" => FieldAttribute::Unique,
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("unknown Field attribute `{ident}`, expected one of `column`, `intermediate`, `try`, `references`, `cascade`, `default`, `skip`, `json`, `flatten`, `collection`, `index` or `unique`"),
+                ))
+            }
         })
     }
 }
+
+/// Type of the elements of a `Vec`, by inspecting the last segment of its path.
+fn element(typename: &Type) -> Option<&Type> {
+    type_argument(typename, "Vec")
+}
+
+/// Type wrapped by an `Option`, by inspecting the last segment of its path.
+pub fn optional(typename: &Type) -> Option<&Type> {
+    type_argument(typename, "Option")
+}
+
+/// Only type argument of the generic type `wrapper`, such as `T` in `Vec<T>`.
+fn type_argument<'t>(typename: &'t Type, wrapper: &str) -> Option<&'t Type> {
+    let Type::Path(path) = typename else {
+        return None;
+    };
+
+    let segment = path
+        .path
+        .segments
+        .last()
+        .filter(|segment| segment.ident == wrapper)?;
+
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    match arguments.args.first() {
+        Some(syn::GenericArgument::Type(element)) if arguments.args.len() == 1 => Some(element),
+        _ => None,
+    }
+}
+
+/// Determines if the type is an `EntityRef`, by inspecting the last segment of its path.
+fn is_entity_ref(typename: &Type) -> bool {
+    let Type::Path(path) = typename else {
+        return false;
+    };
+
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "EntityRef")
+}
@@ -0,0 +1,165 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse::Parse, spanned::Spanned, Data, DeriveInput, Error};
+
+use crate::field::Field;
+
+/// Plain struct whose fields are stored as columns of the components embedding it.
+pub struct Flatten {
+    pub typename: Ident,
+    pub fields: Vec<Field>,
+    /// Fields marked `#[erm(skip)]`, which have no column.
+    pub skipped: Vec<Field>,
+}
+
+impl Flatten {
+    pub fn implementation(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let typename = &self.typename;
+
+        let columns = self
+            .fields
+            .iter()
+            .map(|field| field.column_definition(sqlx, database));
+
+        let definitions = self.fields.iter().map(|field| {
+            let definition = field.sql_type_definition(sqlx, database);
+
+            quote! {
+                format!("{} {}", #definition)
+            }
+        });
+
+        let binds = self.fields.iter().map(Field::serialize);
+
+        let deserialized_fields = self
+            .fields
+            .iter()
+            .map(|field| field.deserialize(sqlx, typename));
+
+        let assignments = self
+            .fields
+            .iter()
+            .chain(&self.skipped)
+            .map(Field::assignment);
+
+        quote! {
+            impl ::erm::flatten::Flatten<#database> for #typename {
+                fn columns() -> Vec<::erm::component::ColumnDefinition::<#database>> {
+                    vec![#(#columns,)*]
+                }
+
+                fn definitions() -> Vec<String> {
+                    use #sqlx::TypeInfo as _;
+
+                    vec![#(#definitions,)*]
+                }
+
+                fn serialize<'q>(
+                    &'q self,
+                    query: #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>>,
+                ) -> #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>> {
+                    #(#binds)*
+
+                    query
+                }
+
+                fn deserialize(row: &mut ::erm::row::OffsetRow<<#database as #sqlx::Database>::Row>) -> Result<Self, #sqlx::Error> {
+                    #(#deserialized_fields;)*
+
+                    Ok(#typename {
+                        #(#assignments,)*
+                    })
+                }
+            }
+        }
+    }
+
+    /// Reflection of the struct, whose columns are named by the prefix given by the
+    /// reflection of the embedding component.
+    pub fn reflection(&self) -> TokenStream {
+        let typename = &self.typename;
+        let reflection_name = Ident::new(&format!("Reflected{typename}"), typename.span());
+
+        let declarations = self.fields.iter().map(Field::reflected_column);
+
+        let constructors = self.fields.iter().map(|field| {
+            let name = field.field_name();
+            let column = field.column_name();
+
+            quote! {
+                #name: ::erm::reflect::ReflectedColumn::prefixed(prefix, #column)
+            }
+        });
+
+        quote! {
+            #[allow(unused)]
+            pub struct #reflection_name {
+                #(#declarations,)*
+            }
+
+            impl #reflection_name {
+                #[allow(unused)]
+                pub const fn prefixed(prefix: &'static str) -> Self {
+                    Self {
+                        #(#constructors,)*
+                    }
+                }
+            }
+
+            impl ::erm::flatten::ReflectFlattened for #typename {
+                type ReflectionType = #reflection_name;
+            }
+        }
+    }
+}
+
+impl Parse for Flatten {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let derive = DeriveInput::parse(input)?;
+
+        let Data::Struct(data) = derive.data else {
+            return Err(Error::new(
+                derive.ident.span(),
+                "Flatten can only be derived for structs",
+            ));
+        };
+
+        if !derive.generics.params.is_empty() {
+            return Err(Error::new(
+                derive.generics.span(),
+                "Flatten cannot be derived for generic structs",
+            ));
+        }
+
+        let (skipped, fields): (Vec<_>, Vec<_>) = Result::<Vec<Field>, _>::from_iter(
+            data.fields.into_iter().enumerate().map(Field::try_from),
+        )?
+        .into_iter()
+        .partition(Field::skip);
+
+        if let Some(field) = fields.iter().find(|field| {
+            field.flatten()
+                || field.collection()
+                || field.references().is_some()
+                || field.index().is_some()
+        }) {
+            return Err(Error::new(
+                field.typename().span(),
+                "flatten, collection, references, index and unique are not supported within flattened structs",
+            ));
+        }
+
+        if fields.is_empty() {
+            return Err(Error::new(
+                derive.ident.span(),
+                "Flatten can only be derived for structs with at least one column",
+            ));
+        }
+
+        Ok(Flatten {
+            typename: derive.ident,
+            fields,
+            skipped,
+        })
+    }
+}
@@ -1,10 +1,13 @@
 mod archetype;
 mod component;
 mod field;
+mod flatten;
 mod reflect;
+mod variant;
 
 use archetype::Archetype;
 use component::Component;
+use flatten::Flatten;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, TokenStreamExt};
 use reflect::reflect_component;
@@ -14,7 +17,10 @@ use syn::spanned::Spanned;
 pub fn derive_component(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let stream = TokenStream::from(stream);
     let span = stream.span();
-    let component: Component = syn::parse2(stream).unwrap();
+    let component: Component = match syn::parse2(stream) {
+        Ok(component) => component,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
     let implementation = |database: Ident, placeholder_char: char| {
         #[cfg(feature = "bundled")]
@@ -28,11 +34,15 @@ pub fn derive_component(stream: proc_macro::TokenStream) -> proc_macro::TokenStr
     };
 
     let mut implementations = implement_for(span, implementation);
-    implementations.append_all(reflect_component(
-        &component.typename,
-        &component.table_name,
-        &component.fields,
-    ));
+    implementations.append_all(reflect_component(&component));
+
+    if component.track_changes {
+        let component_name = &component.typename;
+        let (impl_generics, ty_generics, where_clause) = component.generics.split_for_impl();
+        implementations.append_all(quote! {
+            impl #impl_generics ::erm::component::TrackChanges for #component_name #ty_generics #where_clause {}
+        });
+    }
     implementations.into()
 }
 
@@ -40,7 +50,10 @@ pub fn derive_component(stream: proc_macro::TokenStream) -> proc_macro::TokenStr
 pub fn derive_archetype(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let stream = TokenStream::from(stream);
     let span = stream.span();
-    let archetype: Archetype = syn::parse2(stream).unwrap();
+    let archetype: Archetype = match syn::parse2(stream) {
+        Ok(archetype) => archetype,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
     let implementation = |database: Ident, _: char| {
         #[cfg(feature = "bundled")]
@@ -55,6 +68,30 @@ pub fn derive_archetype(stream: proc_macro::TokenStream) -> proc_macro::TokenStr
     implement_for(span, implementation).into()
 }
 
+#[proc_macro_derive(Flatten, attributes(erm))]
+pub fn derive_flatten(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let stream = TokenStream::from(stream);
+    let span = stream.span();
+    let flatten: Flatten = match syn::parse2(stream) {
+        Ok(flatten) => flatten,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    let implementation = |database: Ident, _: char| {
+        #[cfg(feature = "bundled")]
+        let sqlx = quote! {::erm::sqlx};
+        #[cfg(not(feature = "bundled"))]
+        let sqlx = quote! {::sqlx};
+        let database = quote! {#sqlx::#database};
+
+        flatten.implementation(&sqlx, &database)
+    };
+
+    let mut implementations = implement_for(span, implementation);
+    implementations.append_all(flatten.reflection());
+    implementations.into()
+}
+
 #[allow(unused)]
 fn implement_for(span: Span, implementer: impl Fn(Ident, char) -> TokenStream) -> TokenStream {
     #[allow(unused_mut)]
@@ -71,3 +108,193 @@ fn implement_for(span: Span, implementer: impl Fn(Ident, char) -> TokenStream) -
 
     implementations
 }
+
+#[cfg(test)]
+fn parse_error<T: syn::parse::Parse>(stream: TokenStream) -> String {
+    match syn::parse2::<T>(stream) {
+        Ok(_) => panic!("expected a parse error"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn test_unknown_attributes() {
+    let error = parse_error::<Component>(quote! {
+        #[erm(track_change)]
+        struct Position {
+            x: i64,
+        }
+    });
+    assert!(error.starts_with("unknown Component attribute `track_change`"));
+
+    let error = parse_error::<Component>(quote! {
+        struct Position {
+            #[erm(colum = "x")]
+            x: i64,
+        }
+    });
+    assert!(error.starts_with("unknown Field attribute `colum`"));
+
+    let error = parse_error::<Archetype>(quote! {
+        struct Player {
+            #[erm(cascade)]
+            position: Position,
+        }
+    });
+    assert_eq!(error, "cascade can only be used together with references");
+}
+
+#[test]
+fn test_unsupported_types() {
+    let error = parse_error::<Component>(quote! {
+        union Position {
+            x: i64,
+        }
+    });
+    assert!(error.starts_with("Component can only be derived for structs and enums"));
+
+    let error = parse_error::<Component>(quote! {
+        enum Never {}
+    });
+    assert!(error.starts_with("Component can only be derived for structs and enums"));
+
+    let error = parse_error::<Archetype>(quote! {
+        enum Shape {
+            Circle(Radius, Center),
+        }
+    });
+    assert!(error.starts_with("Archetype variants must hold a single archetype"));
+}
+
+#[test]
+fn test_invalid_fields() {
+    let error = parse_error::<Component>(quote! {
+        struct Owner {
+            #[erm(references = Player)]
+            owner: i64,
+        }
+    });
+    assert_eq!(error, "references can only be used on EntityRef fields");
+
+    let error = parse_error::<Component>(quote! {
+        enum State {
+            Chasing {
+                #[erm(intermediate = i64)]
+                target: u32,
+            },
+        }
+    });
+    assert_eq!(
+        error,
+        "intermediate, json and references are not supported on enum variants"
+    );
+
+    let error = parse_error::<Component>(quote! {
+        #[erm(table = "tagged_{U}")]
+        struct Tagged<T> {
+            value: T,
+        }
+    });
+    assert_eq!(error, "table name refers to unknown type parameter `U`");
+
+    let error = parse_error::<Component>(quote! {
+        struct Cache {
+            #[erm(skip, column = "cached")]
+            cached: Vec<u8>,
+        }
+    });
+    assert!(error.starts_with("skip cannot be combined with other attributes"));
+
+    let error = parse_error::<Component>(quote! {
+        struct Seats {
+            #[erm(try)]
+            seats: NonZeroU32,
+        }
+    });
+    assert_eq!(error, "try can only be used together with intermediate");
+
+    let error = parse_error::<Component>(quote! {
+        enum Movement {
+            RunAway { speed: f64 },
+            Run { away_speed: f64 },
+        }
+    });
+    assert_eq!(
+        error,
+        "`run_away_speed` is used as the column of more than one enum field"
+    );
+
+    let error = parse_error::<Component>(quote! {
+        struct Settings {
+            #[erm(json, intermediate = String)]
+            prefs: Preferences,
+        }
+    });
+    assert_eq!(error, "json cannot be combined with intermediate");
+
+    let error = parse_error::<Component>(quote! {
+        struct Transform<T> {
+            #[erm(flatten)]
+            pos: Vec3,
+            tag: T,
+        }
+    });
+    assert_eq!(error, "flatten is not supported on generic components");
+
+    let error = parse_error::<Component>(quote! {
+        struct Article {
+            #[erm(collection)]
+            tags: HashSet<String>,
+        }
+    });
+    assert_eq!(error, "collection can only be used on Vec fields");
+
+    let error = parse_error::<Component>(quote! {
+        #[erm(history)]
+        struct Article {
+            #[erm(collection)]
+            tags: Vec<String>,
+        }
+    });
+    assert_eq!(
+        error,
+        "collection is not supported on components with history or changelog"
+    );
+
+    let error = parse_error::<Flatten>(quote! {
+        struct Vec3 {
+            #[erm(flatten)]
+            xy: Vec2,
+            z: f64,
+        }
+    });
+    assert_eq!(
+        error,
+        "flatten, collection, references, index and unique are not supported within flattened structs"
+    );
+
+    let error = parse_error::<Component>(quote! {
+        struct Person {
+            #[erm(index, unique)]
+            email: String,
+        }
+    });
+    assert!(error.starts_with("index cannot be combined with unique"));
+
+    let error = parse_error::<Component>(quote! {
+        #[erm(index(fields = [first_name, surname]))]
+        struct Person {
+            first_name: String,
+            last_name: String,
+        }
+    });
+    assert!(error.starts_with("index refers to unknown field `surname`"));
+
+    let error = parse_error::<Component>(quote! {
+        #[erm(unique(columns = [email]))]
+        struct Person {
+            email: String,
+        }
+    });
+    assert_eq!(error, "unknown unique option `columns`, expected `fields`");
+}
@@ -1,44 +1,146 @@
 use proc_macro2::{Ident, TokenStream};
-use quote::quote;
+use quote::{quote, ToTokens};
 
-use crate::field::Field;
+use crate::component::Component;
+
+pub fn reflect_component(component: &Component) -> TokenStream {
+    let component_name = &component.typename;
+    let table_name = &component.table_name;
+    let fields = &component.fields;
+    let tracking = component.tracking_columns();
 
-pub fn reflect_component(
-    component_name: &Ident,
-    table_name: &str,
-    fields: &Vec<Field>,
-) -> TokenStream {
     let reflection_name = Ident::new(&format!("Reflected{component_name}"), component_name.span());
 
-    let declarations = fields.iter().map(Field::reflected_column);
+    let (impl_generics, ty_generics, where_clause) = component.split(component.generics.clone());
+    let (_, _, declared_where_clause) = component.generics.split_for_impl();
+    let declared_generics = &component.generics;
 
-    let constructors = fields.iter().map(|field| {
-        let name = field.field_name();
-        let stringified = format!("__cte_{table_name}__{}", field.column_name());
+    // Type parameters may only be used by the component's fields, and not their columns.
+    let (marker_declaration, marker_constructor) = if component.generics.params.is_empty() {
+        (TokenStream::new(), TokenStream::new())
+    } else {
+        (
+            quote! { _component: ::core::marker::PhantomData<fn() -> #component_name #ty_generics>, },
+            quote! { _component: ::core::marker::PhantomData, },
+        )
+    };
 
-        quote! {
-            #[allow(unused)]
-            #name: ::erm::reflect::ReflectedColumn::new(#stringified)
-        }
-    });
+    let columns: Vec<_> = fields
+        .iter()
+        .map(|field| (field.field_name(), field.column_name().to_string()))
+        .chain(tracking.iter().map(|column| {
+            (
+                Ident::new(column, component_name.span()).into_token_stream(),
+                column.to_string(),
+            )
+        }))
+        .collect();
+
+    let declarations = fields
+        .iter()
+        .map(crate::field::Field::reflected_column)
+        .chain(tracking.iter().map(|column| {
+            let name = Ident::new(column, component_name.span());
+
+            quote! {
+                pub #name: ::erm::reflect::ReflectedColumn<i64>
+            }
+        }));
+
+    // Columns of generic tables are named once the table name is known, at runtime.
+    let (constructors, resolvers): (Vec<_>, Vec<_>) = columns
+        .iter()
+        .map(|(name, column)| {
+            if !component.generic_table() {
+                let stringified = format!("__cte_{table_name}__{column}");
+
+                return (
+                    quote! {
+                        #[allow(unused)]
+                        #name: ::erm::reflect::ReflectedColumn::new(#stringified)
+                    },
+                    TokenStream::new(),
+                );
+            }
+
+            let resolver = Ident::new(&format!("__column_{name}"), component_name.span());
+            let table = component.table_expression();
+            let key = format!("column {column}");
+
+            (
+                quote! {
+                    #[allow(unused)]
+                    #name: ::erm::reflect::ReflectedColumn::generic(Self::#resolver)
+                },
+                quote! {
+                    fn #resolver() -> &'static str {
+                        ::erm::component::intern::<#component_name #ty_generics>(#key, || {
+                            format!("__cte_{}__{}", #table, #column)
+                        })
+                    }
+                },
+            )
+        })
+        .unzip();
+
+    // Flattened structs are reflected by their own type, whose columns are prefixed.
+    let (flattened_declarations, flattened_constructors): (Vec<_>, Vec<_>) = component
+        .flattened
+        .iter()
+        .map(|field| {
+            let name = field.field_name();
+            let typename = field.typename();
+            let prefix = format!("__cte_{table_name}__{}", field.prefix());
+            let reflection =
+                quote! { <#typename as ::erm::flatten::ReflectFlattened>::ReflectionType };
+
+            (
+                quote! { pub #name: #reflection },
+                quote! { #name: <#reflection>::prefixed(#prefix) },
+            )
+        })
+        .unzip();
+
+    let (collection_declarations, collection_constructors): (Vec<_>, Vec<_>) = component
+        .collections
+        .iter()
+        .map(|field| {
+            let name = field.field_name();
+            let element = field.element();
+            let table = format!("{table_name}_{}", field.column_name());
+
+            (
+                quote! { pub #name: ::erm::collection::ReflectedCollection<#element> },
+                quote! { #name: ::erm::collection::ReflectedCollection::new(#table) },
+            )
+        })
+        .unzip();
 
     quote! {
         #[allow(unused)]
-        pub struct #reflection_name {
-            #(#declarations),*
+        pub struct #reflection_name #declared_generics #declared_where_clause {
+            #(#declarations,)*
+            #(#flattened_declarations,)*
+            #(#collection_declarations,)*
+            #marker_declaration
         }
 
-        impl #reflection_name {
+        impl #impl_generics #reflection_name #ty_generics #where_clause {
             #[allow(unused)]
             pub const fn new() -> Self {
                 Self {
                     #(#constructors,)*
+                    #(#flattened_constructors,)*
+                    #(#collection_constructors,)*
+                    #marker_constructor
                 }
             }
+
+            #(#resolvers)*
         }
 
-        impl ::erm::reflect::Reflect for #component_name {
-            type ReflectionType = #reflection_name;
+        impl #impl_generics ::erm::reflect::Reflect for #component_name #ty_generics #where_clause {
+            type ReflectionType = #reflection_name #ty_generics;
             const FIELDS: Self::ReflectionType = #reflection_name::new();
         }
     }
@@ -0,0 +1,276 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse_quote, spanned::Spanned, Error};
+
+use crate::field::{self, Field};
+
+/// Name of the column holding the name of the variant of enum components.
+pub const DISCRIMINANT: &str = "variant";
+
+/// Variant of an enum component.
+///
+/// Enums are stored as a text column holding the name of the variant, followed by
+/// nullable columns for the fields of every variant, named `<variant>_<field>`.
+/// Fields of type `Option<T>` are stored as columns of type `T`, which are null for `None`.
+pub struct Variant {
+    pub ident: Ident,
+    pub style: syn::Fields,
+    pub fields: Vec<Field>,
+    /// Fields marked `#[erm(skip)]`, which have no column.
+    pub skipped: Vec<Field>,
+}
+
+impl Variant {
+    /// Prefix of the columns holding the fields of this variant.
+    fn prefix(&self) -> String {
+        let mut prefix = String::new();
+
+        for (index, character) in self.ident.to_string().chars().enumerate() {
+            if character.is_uppercase() && index != 0 {
+                prefix.push('_');
+            }
+
+            prefix.extend(character.to_lowercase());
+        }
+
+        prefix
+    }
+
+    /// Columns holding the fields of this variant, which are null for any other variant.
+    pub fn columns(&self) -> Vec<Field> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let typename = field::optional(field.typename()).unwrap_or(field.typename());
+
+                Field::column(
+                    format!("{}_{}", self.prefix(), field.column_name()),
+                    typename.clone(),
+                    true,
+                )
+            })
+            .collect()
+    }
+
+    /// Member of the variant holding `field`, such as `target` or `0`.
+    fn member(field: &Field) -> TokenStream {
+        let ident = field.ident();
+        quote! { #ident }
+    }
+
+    /// Binds the value of each column of this variant, or null if `self` is another variant.
+    fn serialize(&self) -> TokenStream {
+        let ident = &self.ident;
+
+        let binds = self.fields.iter().map(|field| {
+            let member = Self::member(field);
+
+            let value = if field::optional(field.typename()).is_some() {
+                quote! { value.as_ref() }
+            } else {
+                quote! { Some(value) }
+            };
+
+            quote! {
+                #[allow(unreachable_patterns)]
+                let query = query.bind(match self {
+                    Self::#ident { #member: value, .. } => #value,
+                    _ => None,
+                });
+            }
+        });
+
+        quote! { #(#binds)* }
+    }
+
+    /// Constructs the variant from the columns read by [`deserialize`].
+    fn constructor(&self, sqlx: &TokenStream) -> TokenStream {
+        let ident = &self.ident;
+
+        let values = self
+            .fields
+            .iter()
+            .zip(self.columns())
+            .map(|(field, column)| {
+                let member = Self::member(field);
+                let value = column.ident();
+                let name = column.column_name();
+
+                match field.default() {
+                    _ if field::optional(field.typename()).is_some() => quote! {
+                        #member: #value?
+                    },
+                    Some(default) => quote! {
+                        #member: #value?.unwrap_or_else(|| #default)
+                    },
+                    None => quote! {
+                        #member: #value?.ok_or_else(|| #sqlx::Error::ColumnDecode {
+                            index: #name.to_string(),
+                            source: "unexpected null".into(),
+                        })?
+                    },
+                }
+            });
+
+        let skipped = self.skipped.iter().map(Field::assignment);
+
+        match self.style {
+            syn::Fields::Unit => quote! { Self::#ident },
+            _ => quote! { Self::#ident { #(#values,)* #(#skipped,)* } },
+        }
+    }
+}
+
+/// Ensures no two fields of the enum are stored in the same column, since the columns of
+/// `FooBar { baz }` and `Foo { bar_baz }` are both named `foo_bar_baz`.
+pub fn check_columns(variants: &[Variant]) -> Result<(), Error> {
+    let mut columns = Vec::new();
+
+    for variant in variants {
+        for (field, column) in variant.fields.iter().zip(variant.columns()) {
+            let column = column.column_name().to_string();
+
+            if columns.contains(&column) {
+                return Err(Error::new(
+                    field.typename().span(),
+                    format!("`{column}` is used as the column of more than one enum field"),
+                ));
+            }
+
+            columns.push(column);
+        }
+    }
+
+    Ok(())
+}
+
+/// Columns of an enum component, starting with its discriminant.
+pub fn columns(variants: &[Variant]) -> Vec<Field> {
+    std::iter::once(Field::column(
+        DISCRIMINANT.to_string(),
+        parse_quote!(String),
+        false,
+    ))
+    .chain(variants.iter().flat_map(Variant::columns))
+    .collect()
+}
+
+pub fn serialize(variants: &[Variant]) -> TokenStream {
+    let discriminants = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let name = ident.to_string();
+
+        quote! {
+            Self::#ident { .. } => #name
+        }
+    });
+
+    let binds = variants.iter().map(Variant::serialize);
+
+    quote! {
+        let query = query.bind(match self {
+            #(#discriminants,)*
+        });
+
+        #(#binds)*
+    }
+}
+
+/// Reads every column of the enum, and constructs the variant named by the discriminant.
+pub fn deserialize(variants: &[Variant], sqlx: &TokenStream, skip_tracking: usize) -> TokenStream {
+    let columns = variants.iter().flat_map(Variant::columns).map(|column| {
+        let ident = column.ident();
+        let typename = column.typename();
+
+        quote! {
+            let #ident = row.try_get::<Option<#typename>>();
+        }
+    });
+
+    let arms = variants.iter().map(|variant| {
+        let name = variant.ident.to_string();
+        let constructor = variant.constructor(sqlx);
+
+        quote! {
+            #name => #constructor
+        }
+    });
+
+    quote! {
+        let variant = row.try_get::<String>();
+        #(#columns)*
+
+        row.skip(#skip_tracking);
+
+        let variant = variant?;
+        let component = match variant.as_str() {
+            #(#arms,)*
+            unknown => {
+                return Err(#sqlx::Error::ColumnDecode {
+                    index: "variant".to_string(),
+                    source: format!("unknown variant {unknown}").into(),
+                })
+            }
+        };
+
+        Ok(component)
+    }
+}
+
+impl TryFrom<syn::Variant> for Variant {
+    type Error = syn::Error;
+
+    fn try_from(variant: syn::Variant) -> Result<Self, Self::Error> {
+        let (skipped, fields): (Vec<_>, Vec<_>) = Result::<Vec<Field>, _>::from_iter(
+            variant
+                .fields
+                .clone()
+                .into_iter()
+                .enumerate()
+                .map(Field::try_from),
+        )?
+        .into_iter()
+        .partition(Field::skip);
+
+        if let Some(field) = fields
+            .iter()
+            .find(|field| field.intermediate().is_some() || field.references().is_some())
+        {
+            return Err(Error::new(
+                field.typename().span(),
+                "intermediate, json and references are not supported on enum variants",
+            ));
+        }
+
+        if let Some(field) = fields
+            .iter()
+            .find(|field| field.flatten() || field.collection() || field.index().is_some())
+        {
+            return Err(Error::new(
+                field.typename().span(),
+                "flatten, collection, index and unique are not supported on enum variants",
+            ));
+        }
+
+        let variant = Variant {
+            ident: variant.ident,
+            style: variant.fields,
+            fields,
+            skipped,
+        };
+
+        // Columns double as the names of the bindings used to deserialize them.
+        for field in &variant.fields {
+            let column = format!("{}_{}", variant.prefix(), field.column_name());
+
+            if syn::parse_str::<Ident>(&column).is_err() {
+                return Err(Error::new(
+                    field.typename().span(),
+                    format!("`{column}` is not a valid column name for an enum variant"),
+                ));
+            }
+        }
+
+        Ok(variant)
+    }
+}
@@ -0,0 +1,11 @@
+use erm_derive::Archetype;
+
+struct Circle;
+struct Square;
+
+#[derive(Archetype)]
+enum Shape {
+    Circle(Circle, Square),
+}
+
+fn main() {}
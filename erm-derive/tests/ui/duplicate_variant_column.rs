@@ -0,0 +1,9 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+enum Movement {
+    RunAway { speed: f64 },
+    Run { away_speed: f64 },
+}
+
+fn main() {}
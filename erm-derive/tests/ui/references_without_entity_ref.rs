@@ -0,0 +1,11 @@
+use erm_derive::Component;
+
+struct Player;
+
+#[derive(Component)]
+struct Owner {
+    #[erm(references = Player)]
+    owner: i64,
+}
+
+fn main() {}
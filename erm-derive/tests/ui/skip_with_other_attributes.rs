@@ -0,0 +1,9 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+struct Cache {
+    #[erm(skip, column = "cached")]
+    cached: Vec<u8>,
+}
+
+fn main() {}
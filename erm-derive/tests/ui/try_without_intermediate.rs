@@ -0,0 +1,11 @@
+use std::num::NonZeroU32;
+
+use erm_derive::Component;
+
+#[derive(Component)]
+struct Seats {
+    #[erm(try)]
+    seats: NonZeroU32,
+}
+
+fn main() {}
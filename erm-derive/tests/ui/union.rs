@@ -0,0 +1,8 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+union Position {
+    x: i64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+#[erm(track_change)]
+struct Position {
+    x: i64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+struct Position {
+    #[erm(colum = "x")]
+    x: i64,
+}
+
+fn main() {}
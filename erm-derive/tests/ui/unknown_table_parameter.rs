@@ -0,0 +1,9 @@
+use erm_derive::Component;
+
+#[derive(Component)]
+#[erm(table = "tagged_{U}")]
+struct Tagged<T> {
+    value: T,
+}
+
+fn main() {}
@@ -0,0 +1,204 @@
+//! Aggregate functions which can be computed over the columns of a [`List`](crate::backend::List) query.
+//!
+//! Aggregates are evaluated by the database against the output columns of the
+//! query's common table expression, so only the result is transferred.
+
+use std::fmt::Write;
+
+use sqlx::Database;
+
+use crate::reflect::ReflectedColumn;
+
+/// Database-specific SQL for aggregates whose result type differs between databases.
+///
+/// Postgres sums `bigint` columns as `numeric`, and MySQL sums integers and averages
+/// any column as `decimal`, so their results are cast back to the expected type.
+pub trait NumericAggregate: Database {
+    /// Sum of `column`, as the SQL type `type_info` of the column itself.
+    fn sum(column: &str, type_info: &Self::TypeInfo) -> String;
+
+    /// Average of `column`, as a double precision float.
+    fn avg(column: &str) -> String;
+}
+
+#[cfg(feature = "sqlite")]
+impl NumericAggregate for sqlx::Sqlite {
+    fn sum(column: &str, _: &Self::TypeInfo) -> String {
+        format!("sum({column})")
+    }
+
+    fn avg(column: &str) -> String {
+        format!("avg({column})")
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl NumericAggregate for sqlx::Postgres {
+    fn sum(column: &str, type_info: &Self::TypeInfo) -> String {
+        use sqlx::TypeInfo as _;
+
+        format!("cast(sum({column}) as {})", type_info.name())
+    }
+
+    fn avg(column: &str) -> String {
+        format!("cast(avg({column}) as double precision)")
+    }
+}
+
+/// Casts in MySQL only accept a few type names, such as `signed` in place of `bigint`.
+#[cfg(feature = "mysql")]
+impl NumericAggregate for sqlx::MySql {
+    fn sum(column: &str, type_info: &Self::TypeInfo) -> String {
+        use sqlx::TypeInfo as _;
+
+        let name = type_info.name();
+
+        if name.contains("INT") && name.ends_with("UNSIGNED") {
+            format!("cast(sum({column}) as unsigned)")
+        } else if name.contains("INT") {
+            format!("cast(sum({column}) as signed)")
+        } else if name == "FLOAT" {
+            format!("cast(sum({column}) as float)")
+        } else {
+            format!("sum({column})")
+        }
+    }
+
+    fn avg(column: &str) -> String {
+        format!("cast(avg({column}) as double)")
+    }
+}
+
+/// Describes an aggregate expression and the type it decodes into.
+pub trait Aggregate<DB: Database> {
+    type Output: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result;
+}
+
+/// Sum of all values in a column. Yields `None` if no rows matched.
+pub struct Sum<T> {
+    column: ReflectedColumn<T>,
+}
+
+impl<T> Sum<T> {
+    pub const fn new(column: ReflectedColumn<T>) -> Self {
+        Sum { column }
+    }
+}
+
+impl<DB: NumericAggregate, T: sqlx::Type<DB>> Aggregate<DB> for Sum<T>
+where
+    Option<T>: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+{
+    type Output = Option<T>;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(f, "{}", DB::sum(self.column.column_name(), &T::type_info()))
+    }
+}
+
+/// Average of all values in a column. Yields `None` if no rows matched.
+pub struct Avg<T> {
+    column: ReflectedColumn<T>,
+}
+
+impl<T> Avg<T> {
+    pub const fn new(column: ReflectedColumn<T>) -> Self {
+        Avg { column }
+    }
+}
+
+impl<DB: NumericAggregate, T> Aggregate<DB> for Avg<T>
+where
+    Option<f64>: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+{
+    type Output = Option<f64>;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(f, "{}", DB::avg(self.column.column_name()))
+    }
+}
+
+/// Smallest value in a column. Yields `None` if no rows matched.
+pub struct Min<T> {
+    column: ReflectedColumn<T>,
+}
+
+impl<T> Min<T> {
+    pub const fn new(column: ReflectedColumn<T>) -> Self {
+        Min { column }
+    }
+}
+
+impl<DB: Database, T> Aggregate<DB> for Min<T>
+where
+    Option<T>: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+{
+    type Output = Option<T>;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(f, "min({})", self.column.column_name())
+    }
+}
+
+/// Largest value in a column. Yields `None` if no rows matched.
+pub struct Max<T> {
+    column: ReflectedColumn<T>,
+}
+
+impl<T> Max<T> {
+    pub const fn new(column: ReflectedColumn<T>) -> Self {
+        Max { column }
+    }
+}
+
+impl<DB: Database, T> Aggregate<DB> for Max<T>
+where
+    Option<T>: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+{
+    type Output = Option<T>;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(f, "max({})", self.column.column_name())
+    }
+}
+
+/// Number of matching entities.
+pub struct Count;
+
+impl<DB: Database> Aggregate<DB> for Count
+where
+    i64: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+{
+    type Output = i64;
+
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(f, "count(*)")
+    }
+}
+
+/// Sum all values of `column`.
+pub const fn sum<T>(column: ReflectedColumn<T>) -> Sum<T> {
+    Sum::new(column)
+}
+
+/// Average all values of `column`.
+pub const fn avg<T>(column: ReflectedColumn<T>) -> Avg<T> {
+    Avg::new(column)
+}
+
+/// Find the smallest value of `column`.
+pub const fn min<T>(column: ReflectedColumn<T>) -> Min<T> {
+    Min::new(column)
+}
+
+/// Find the largest value of `column`.
+pub const fn max<T>(column: ReflectedColumn<T>) -> Max<T> {
+    Max::new(column)
+}
+
+/// Count the number of matching entities.
+pub const fn count() -> Count {
+    Count
+}
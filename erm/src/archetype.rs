@@ -10,6 +10,14 @@ use crate::{
 
 pub trait DatabasePlaceholder {
     const PLACEHOLDER: char = '?';
+
+    /// Placeholder for the `index`th parameter of a query, starting from 1.
+    fn placeholder(index: usize) -> String {
+        match Self::PLACEHOLDER {
+            '$' => format!("${index}"),
+            placeholder => placeholder.to_string(),
+        }
+    }
 }
 
 #[cfg(feature = "sqlite")]
@@ -28,26 +36,28 @@ pub trait Archetype<DB: Database>: Deserializeable<DB> + Sized {
         &'query self,
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'query
     where
-        Self: Serializable<DB> + Send,
+        Self: Serializable<DB> + Send + Sync,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
             IntoArguments<'connection, DB> + Send,
         for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
             Executor<'connection, Database = DB>,
-        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'query,
     {
         let mut inserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
 
-        <Self as Serializable<DB>>::insert(&self, &mut inserts);
+        <Self as Serializable<DB>>::insert(self, &mut inserts);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
+            let mut tx = pool.begin().await?;
             for query in inserts.queries {
-                query.execute(&mut *tx).await.unwrap();
+                query.execute(&mut *tx).await?;
             }
 
-            tx.commit().await.unwrap();
+            <Self as Serializable<DB>>::on_insert(self, inserts.entity, &mut *tx).await?;
+
+            tx.commit().await
         }
     }
 
@@ -55,52 +65,57 @@ pub trait Archetype<DB: Database>: Deserializeable<DB> + Sized {
         &'query self,
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'query
     where
-        Self: Serializable<DB> + Send,
+        Self: Serializable<DB> + Send + Sync,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
             IntoArguments<'connection, DB> + Send,
         for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
             Executor<'connection, Database = DB>,
-        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'query,
     {
         let mut inserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
 
-        <Self as Serializable<DB>>::update(&self, &mut inserts);
+        <Self as Serializable<DB>>::update(self, &mut inserts);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
+            let mut tx = pool.begin().await?;
             for query in inserts.queries {
-                query.execute(&mut *tx).await.unwrap();
+                query.execute(&mut *tx).await?;
             }
 
-            tx.commit().await.unwrap();
+            <Self as Serializable<DB>>::on_update(self, inserts.entity, &mut *tx).await?;
+
+            tx.commit().await
         }
     }
 
     fn remove<'query, EntityId>(
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'query
     where
         Self: Removable<DB> + Send,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
             IntoArguments<'connection, DB> + Send,
         for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
             Executor<'connection, Database = DB>,
-        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'query,
     {
         let mut removes = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
 
         <Self as Removable<DB>>::remove(&mut removes);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
+            let mut tx = pool.begin().await?;
+
+            <Self as Removable<DB>>::on_remove(removes.entity.clone(), &mut *tx).await?;
+
             for query in removes.queries {
-                query.execute(&mut *tx).await.unwrap();
+                query.execute(&mut *tx).await?;
             }
 
-            tx.commit().await.unwrap();
+            tx.commit().await
         }
     }
 }
@@ -135,11 +150,4 @@ macro_rules! impl_compound {
     };
 }
 
-impl_compound!(A:0, B:1);
-impl_compound!(A:0, B:1, C:2);
-impl_compound!(A:0, B:1, C:2, D:3);
-impl_compound!(A:0, B:1, C:2, D:3, E:4);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+for_tuples!(impl_compound);
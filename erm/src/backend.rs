@@ -1,4 +1,10 @@
-use std::{future::Future, marker::PhantomData};
+use std::{
+    collections::HashSet,
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
 
 use async_stream::stream;
 use futures::Stream;
@@ -8,12 +14,22 @@ use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Pool};
 use uuid::Uuid;
 
 use crate::{
-    archetype::Archetype,
+    aggregate::{Aggregate, Avg, Count, Max, Min, Sum},
+    archetype::{Archetype, DatabasePlaceholder},
+    changelog::{Change, Changelog},
+    component::{SoftDelete, TrackChanges, Versioned},
     condition::{All, Condition},
-    cte::{Filter, With, Without},
+    cte::{
+        Added, Changed, Direction, FetchRef, Filter, Hierarchy, JoinRef, Lock, Merge, Referenced,
+        RowLocking, Scope, Scoped, Traversal, With, Without,
+    },
+    entity::{EntityPrefixedQuery, EntityRef},
     prelude::{Component, Deserializeable, Serializable},
-    row::Entity,
+    reflect::ReflectedColumn,
+    row::{Dereferenced, Entity, Related},
+    subscription::{Event, Notifier, Notify, Received},
     tables::Removable,
+    version::{RowsAffected, UpdateError, Version},
 };
 
 #[cfg(feature = "sqlite")]
@@ -57,40 +73,94 @@ where
     where
         T: Component<DB>;
 
-    fn spawn<'a, T>(&'a self, components: &'a T) -> impl Future<Output = EntityId> + 'a
+    fn spawn<'a, T>(
+        &'a self,
+        components: &'a T,
+    ) -> impl Future<Output = Result<EntityId, sqlx::Error>> + 'a
     where
         EntityId: GenerateNew,
-        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + Sync + 'static,
     {
         async move {
             let entity = EntityId::generate_new();
-            self.insert(&entity, components).await;
-            entity
+            self.insert(&entity, components).await?;
+            Ok(entity)
         }
     }
 
+    /// Insert the components `T` into the entity.
+    ///
+    /// Fails if any of the statements, such as one violating a unique index, or any of
+    /// the components' hooks fail, in which case none of the components are inserted.
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
-        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + Sync + 'static;
 
+    /// Update the components `T` of the entity.
+    ///
+    /// Like [`Backend::insert`], either all of the components are updated or none are.
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
-        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + Sync + 'static;
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    /// Update the component `T` of the entity, if it is still at the version it was read at.
+    ///
+    /// Returns the new version of the component, or [`UpdateError::Conflict`] if it
+    /// was updated or removed since.
+    fn update_versioned<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        component: &'a Version<T>,
+    ) -> impl Future<Output = Result<i64, UpdateError>> + 'a
+    where
+        DB: RowsAffected,
+        T: Versioned<DB> + Unpin + Send + Sync + 'static;
+
+    /// Remove the components `T` from the entity.
+    ///
+    /// Like [`Backend::insert`], either all of the components are removed or none are.
+    fn remove<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
         T: Archetype<DB> + Removable<DB> + Unpin + Send + 'static;
 
+    /// Restore the soft-deleted component `T` of the entity.
+    ///
+    /// Does nothing if the entity has no soft-deleted `T`.
+    fn restore<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        T: SoftDelete<DB> + Unpin + Send + 'static;
+
+    /// Remove every registered component from the entity.
+    ///
+    /// Components are removed as by [`Backend::remove`], so those deriving `#[erm(soft_delete)]`
+    /// are marked as deleted, and removals are kept in the history and changelog of
+    /// components deriving `#[erm(history)]` and `#[erm(changelog)]`.
+    ///
+    /// Entities referencing it through a field marked `#[erm(cascade)]` are
+    /// despawned along with it, all within a single transaction.
+    fn despawn<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        EntityId: PartialEq;
+
     /// Query the backend for entities.
     ///
     /// See [`List`] for options allowng further filtering and processing.
@@ -99,6 +169,320 @@ where
     fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
     where
         T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// Recursively list all descendants of `root`, where the column `parent` of the
+    /// components `P` holds the ID of each entity's parent.
+    ///
+    /// Each entity is returned once, along with its depth below `root`, and only
+    /// descendants containing the components `T` are returned. Entities without `P`
+    /// are not walked through, and cycles in the hierarchy are only followed once.
+    fn descendants_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<DB>,
+        T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// Recursively list all ancestors of `root`, where the column `parent` of the
+    /// components `P` holds the ID of each entity's parent.
+    ///
+    /// Each entity is returned once, along with its depth above `root`, and only
+    /// ancestors containing the components `T` are returned. Entities without `P`
+    /// are not walked through, and cycles in the hierarchy are only followed once.
+    fn ancestors_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<DB>,
+        T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// List the changes recorded for the component `T` with a sequence number above `after`,
+    /// in the order they were made. Pass `0` to read the entire changelog.
+    ///
+    /// Only components deriving `#[erm(changelog)]` are recorded.
+    fn changelog<'a, T>(
+        &'a self,
+        after: i64,
+    ) -> impl Stream<Item = Result<Change<EntityId, T>, sqlx::Error>> + 'a
+    where
+        DB: Changelog,
+        T: Component<DB> + Unpin + Send + 'static;
+}
+
+/// Component table recorded by [`Backend::register`].
+pub(crate) struct Registration<DB: Database, EntityId> {
+    table: &'static str,
+    /// Columns whose rows are despawned along with the entity they reference.
+    cascades: Vec<&'static str>,
+    /// Queues the removal of the component, see [`Component::remove_component`].
+    remove: for<'q> fn(&mut EntityPrefixedQuery<'q, DB, &'q EntityId>),
+}
+
+impl<DB: Database, EntityId> Clone for Registration<DB, EntityId> {
+    fn clone(&self) -> Self {
+        Registration {
+            table: self.table,
+            cascades: self.cascades.clone(),
+            remove: self.remove,
+        }
+    }
+}
+
+/// Component tables known to a backend.
+pub(crate) struct Registry<DB: Database, EntityId>(Arc<RwLock<Vec<Registration<DB, EntityId>>>>);
+
+impl<DB: Database, EntityId> Default for Registry<DB, EntityId> {
+    fn default() -> Self {
+        Registry(Arc::default())
+    }
+}
+
+impl<DB: Database, EntityId> Registry<DB, EntityId> {
+    pub fn register<T: Component<DB>>(&self)
+    where
+        for<'e> EntityId: sqlx::Encode<'e, DB> + sqlx::Type<DB>,
+    {
+        fn remove<'q, DB, EntityId, T>(query: &mut EntityPrefixedQuery<'q, DB, &'q EntityId>)
+        where
+            DB: Database,
+            T: Component<DB>,
+            for<'e> EntityId: sqlx::Encode<'e, DB> + sqlx::Type<DB>,
+        {
+            <T as Component<DB>>::remove_component(query)
+        }
+
+        let registration = Registration {
+            table: <T as Component<DB>>::table(),
+            cascades: <T as Component<DB>>::columns()
+                .iter()
+                .filter(|column| column.cascade())
+                .map(|column| column.name())
+                .collect(),
+            remove: remove::<DB, EntityId, T>,
+        };
+
+        let mut components = self.0.write().unwrap();
+        if !components
+            .iter()
+            .any(|component| component.table == registration.table)
+        {
+            components.push(registration);
+        }
+    }
+
+    pub fn components(&self) -> Vec<Registration<DB, EntityId>> {
+        self.0.read().unwrap().clone()
+    }
+}
+
+/// Removes every registered component from `entity`, as well as from all entities
+/// referencing it through a cascading reference, recursively.
+pub(crate) async fn despawn<DB, EntityId>(
+    pool: &Pool<DB>,
+    registry: &Registry<DB, EntityId>,
+    entity: &EntityId,
+) -> Result<(), sqlx::Error>
+where
+    DB: Database + DatabasePlaceholder,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId:
+        sqlx::Decode<'e, DB> + sqlx::Encode<'e, DB> + sqlx::Type<DB> + PartialEq + Unpin + Send,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    let components = registry.components();
+
+    let mut tx = pool.begin().await?;
+
+    // Find all referencing entities before removing anything, since foreign keys
+    // with "on delete cascade" would otherwise remove the references we follow.
+    let mut pending = referencing(&mut tx, &components, entity).await?;
+    let mut children: Vec<EntityId> = vec![];
+
+    while let Some(child) = pending.pop() {
+        if &child == entity || children.contains(&child) {
+            continue;
+        }
+
+        pending.extend(referencing(&mut tx, &components, &child).await?);
+        children.push(child);
+    }
+
+    // Entities are removed before the ones they reference, so that their components are
+    // still around to be recorded in the changelog and history, rather than cascaded away.
+    for entity in children.iter().rev().chain([entity]) {
+        let mut removes = EntityPrefixedQuery::<'_, DB, &EntityId>::new(entity);
+
+        for component in &components {
+            (component.remove)(&mut removes);
+        }
+
+        for query in removes.queries {
+            query.execute(&mut *tx).await?;
+        }
+    }
+
+    tx.commit().await
+}
+
+/// Finds all entities referencing `entity` through a cascading reference.
+async fn referencing<DB, EntityId>(
+    connection: &mut <DB as Database>::Connection,
+    components: &[Registration<DB, EntityId>],
+    entity: &EntityId,
+) -> Result<Vec<EntityId>, sqlx::Error>
+where
+    DB: Database + DatabasePlaceholder,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Decode<'e, DB> + sqlx::Encode<'e, DB> + sqlx::Type<DB> + Unpin + Send,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    let placeholder = <DB as DatabasePlaceholder>::placeholder(1);
+    let mut entities = vec![];
+
+    for component in components {
+        for column in &component.cascades {
+            let sql = format!(
+                "select entity from {} where {column} = {placeholder}",
+                component.table
+            );
+
+            let rows: Vec<(EntityId,)> = sqlx::query_as(&sql)
+                .bind(entity)
+                .fetch_all(&mut *connection)
+                .await?;
+
+            entities.extend(rows.into_iter().map(|(entity,)| entity));
+        }
+    }
+
+    Ok(entities)
+}
+
+/// Walks the hierarchy described by the column `parent` of the components `P`, starting at `root`.
+pub(crate) fn traverse<'a, DB, EntityId, P, T>(
+    pool: &'a Pool<DB>,
+    parent: ReflectedColumn<EntityRef<EntityId>>,
+    root: &'a EntityId,
+    direction: Direction,
+) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+where
+    DB: Traversal,
+    P: Deserializeable<DB>,
+    T: Deserializeable<DB> + Unpin + Send + 'static,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Decode<'e, DB> + sqlx::Encode<'e, DB> + sqlx::Type<DB> + Unpin + Send,
+    i64: for<'e> sqlx::Decode<'e, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    stream! {
+        let hierarchy = Hierarchy::new::<DB>(
+            <P as Deserializeable<DB>>::cte(),
+            parent.column_name(),
+            direction,
+        );
+
+        let cte = Merge {
+            tables: vec![Box::new(hierarchy), <T as Deserializeable<DB>>::cte()],
+        };
+
+        let sql = crate::cte::serialize(&cte).unwrap();
+
+        let query = sqlx::query_as::<DB, Related<EntityId, T>>(&sql).bind(root);
+
+        for await result in query.fetch(pool) {
+            yield result;
+        }
+    }
+}
+
+pub(crate) async fn update_versioned<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    entity: &EntityId,
+    component: &Version<T>,
+) -> Result<i64, UpdateError>
+where
+    DB: RowsAffected,
+    T: Versioned<DB> + Sync,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Encode<'e, DB> + sqlx::Type<DB>,
+    for<'e> &'e EntityId: Send,
+{
+    let mut updates = EntityPrefixedQuery::<'_, DB, &EntityId>::new(entity);
+
+    <T as Versioned<DB>>::update_version(component.component(), component.version(), &mut updates);
+
+    let mut tx = pool.begin().await?;
+
+    // Dropping the transaction rolls it back, should the update conflict.
+    for (index, query) in updates.queries.into_iter().enumerate() {
+        let result = query.execute(&mut *tx).await?;
+
+        if updates.guarded == Some(index) && <DB as RowsAffected>::rows_affected(&result) == 0 {
+            return Err(UpdateError::Conflict);
+        }
+    }
+
+    <T as Serializable<DB>>::on_update(component.component(), updates.entity, &mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(component.version() + 1)
+}
+
+pub(crate) async fn restore<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    entity: &EntityId,
+) -> Result<(), sqlx::Error>
+where
+    DB: Database,
+    T: SoftDelete<DB>,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Encode<'e, DB> + sqlx::Type<DB>,
+{
+    let mut restores = EntityPrefixedQuery::<'_, DB, &EntityId>::new(entity);
+
+    <T as SoftDelete<DB>>::restore_component(&mut restores);
+
+    let mut tx = pool.begin().await?;
+
+    for query in restores.queries {
+        query.execute(&mut *tx).await?;
+    }
+
+    tx.commit().await
+}
+
+pub(crate) fn changes<'a, DB, EntityId, T>(
+    pool: &'a Pool<DB>,
+    after: i64,
+) -> impl Stream<Item = Result<Change<EntityId, T>, sqlx::Error>> + 'a
+where
+    DB: Changelog,
+    T: Component<DB> + Unpin + Send + 'static,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Decode<'e, DB> + sqlx::Type<DB> + Unpin + Send + 'static,
+    for<'e> i64: sqlx::Decode<'e, DB> + sqlx::Encode<'e, DB> + sqlx::Type<DB>,
+    String: for<'e> sqlx::Decode<'e, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    stream! {
+        let sql = crate::changelog::read::<DB, T>();
+
+        let query = sqlx::query_as::<DB, Change<EntityId, T>>(&sql).bind(after);
+
+        for await result in query.fetch(pool) {
+            yield result;
+        }
+    }
 }
 
 /// Configurable query for entities.
@@ -114,11 +498,15 @@ pub struct List<
     DB: Database,
 {
     pool: Pool<DB>,
-    _data: PhantomData<(EntityId, T, F, Out)>,
+    notifier: Notifier,
+    _data: PhantomData<(EntityId, T, Out)>,
     map: Map,
+    filters: F,
     condition: C,
+    limit: Option<u64>,
 }
 
+#[allow(clippy::type_complexity)]
 impl<DB, EntityId, T, F, C, Out, Map> List<DB, EntityId, T, F, C, Out, Map>
 where
     DB: Database,
@@ -134,8 +522,11 @@ where
     ) -> List<DB, EntityId, T, (With<Include>, F), C, Out, Map> {
         List {
             pool: self.pool,
+            notifier: self.notifier,
             _data: PhantomData,
+            filters: (With::default(), self.filters),
             condition: self.condition,
+            limit: self.limit,
             map: self.map,
         }
     }
@@ -150,8 +541,204 @@ where
     ) -> List<DB, EntityId, T, (Without<Exclude>, F), C, Out, Map> {
         List {
             pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: (Without::default(), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Include only entities whose component `Tracked` was inserted or updated after `tick`.
+    ///
+    /// Requires `Tracked` to derive `#[erm(track_changes)]`.
+    pub fn changed<Tracked: TrackChanges>(
+        self,
+        tick: i64,
+    ) -> List<DB, EntityId, T, (Changed<Tracked>, F), C, Out, Map> {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: (Changed::since(tick), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Include only entities whose component `Tracked` was inserted after `tick`.
+    ///
+    /// Requires `Tracked` to derive `#[erm(track_changes)]`.
+    pub fn added<Tracked: TrackChanges>(
+        self,
+        tick: i64,
+    ) -> List<DB, EntityId, T, (Added<Tracked>, F), C, Out, Map> {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: (Added::since(tick), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Read components deriving `#[erm(history)]` as they were at `timestamp`,
+    /// in milliseconds since the unix epoch.
+    ///
+    /// Applies to every component read by the query, including those only used for
+    /// filtering. Components without history are read in their current state.
+    pub fn as_of(self, timestamp: i64) -> List<DB, EntityId, T, Scoped<F>, C, Out, Map> {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: Scoped::new(Scope::AsOf(timestamp), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Include components deriving `#[erm(soft_delete)]` which have been removed.
+    ///
+    /// Applies to every component read by the query, including those only used for filtering.
+    pub fn include_deleted(self) -> List<DB, EntityId, T, Scoped<F>, C, Out, Map> {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: Scoped::new(Scope::IncludeDeleted, self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Lock the components of the entities returned by the query until the end of the
+    /// transaction, waiting for any rows locked by other transactions to be released.
+    ///
+    /// Only the rows of the returned entities are locked, and only for components every one
+    /// of them has, so optional, excluded and referenced components are not locked.
+    ///
+    /// Locks are released as soon as the query completes, unless it is executed within
+    /// a transaction using [`List::fetch_in`]. See [`List::for_update_skip_locked`]
+    /// for claiming entities from a pool of workers.
+    ///
+    /// Not available on SQLite, which serializes all writes to the database instead.
+    pub fn for_update(self) -> List<DB, EntityId, T, Scoped<F>, C, Out, Map>
+    where
+        DB: RowLocking,
+    {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: Scoped::new(Scope::Lock(Lock::ForUpdate), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Lock the components of the entities returned by the query until the end of the
+    /// transaction, skipping entities whose rows are already locked by other transactions.
+    ///
+    /// Locks the same components as [`List::for_update`], and an entity is skipped if any
+    /// of them are locked.
+    ///
+    /// ```rust,ignore
+    /// # use erm::prelude::*;
+    /// # use futures::TryStreamExt as _;
+    /// # #[derive(Component)]
+    /// # struct Job { name: String }
+    /// # #[derive(Component)]
+    /// # struct Pending;
+    /// # async fn example(pool: sqlx::PgPool, backend: PostgresBackend<i64>) {
+    /// let mut tx = pool.begin().await.unwrap();
+    ///
+    /// // Claim a batch of pending jobs, which no other worker is processing.
+    /// let jobs = backend
+    ///     .list::<Job>()
+    ///     .with::<Pending>()
+    ///     .for_update_skip_locked()
+    ///     .limit(10)
+    ///     .fetch_in(&mut *tx)
+    ///     .try_collect::<Vec<_>>()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// // ... process the jobs, then release the locks.
+    /// tx.commit().await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Not available on SQLite, which serializes all writes to the database instead.
+    pub fn for_update_skip_locked(self) -> List<DB, EntityId, T, Scoped<F>, C, Out, Map>
+    where
+        DB: RowLocking,
+    {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            filters: Scoped::new(Scope::Lock(Lock::ForUpdateSkipLocked), self.filters),
+            condition: self.condition,
+            limit: self.limit,
+            map: self.map,
+        }
+    }
+
+    /// Return at most `limit` entities, such as a batch of entities claimed by a worker
+    /// using [`List::for_update_skip_locked`].
+    ///
+    /// Applies to [`List::fetch`] and [`List::fetch_in`].
+    pub fn limit(self, limit: u64) -> Self {
+        List {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Include only entities whose reference `column` points to an entity
+    /// matching the filters applied by `join`.
+    ///
+    /// See [`List::fetch_ref`] for also reading components of the referenced entity.
+    ///
+    /// ```rust
+    /// # use erm::prelude::*;
+    /// # #[derive(Component)]
+    /// # struct Item;
+    /// # #[derive(Component)]
+    /// # struct Player;
+    /// #[derive(Component)]
+    /// struct Owner(EntityRef<i64>);
+    ///
+    /// # async fn example(backend: SqliteBackend<i64>) {
+    /// // Items owned by players.
+    /// let items = backend
+    ///     .list::<(Item, Owner)>()
+    ///     .join_ref(Owner::FIELDS.self_0, |owner| owner.with::<Player>());
+    /// # }
+    /// ```
+    pub fn join_ref<J>(
+        self,
+        column: ReflectedColumn<EntityRef<EntityId>>,
+        join: impl FnOnce(Referenced) -> Referenced<J>,
+    ) -> List<DB, EntityId, T, (JoinRef<J>, F), C, Out, Map> {
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
             _data: PhantomData,
+            filters: (
+                JoinRef::new(column.column_name(), join(Referenced::new()).into_filter()),
+                self.filters,
+            ),
             condition: self.condition,
+            limit: self.limit,
             map: self.map,
         }
     }
@@ -166,8 +753,11 @@ where
     ) -> List<DB, EntityId, T, F, Cond, Out, Map> {
         List {
             pool: self.pool,
+            notifier: self.notifier,
             _data: PhantomData,
+            filters: self.filters,
             condition,
+            limit: self.limit,
             map: self.map,
         }
     }
@@ -179,8 +769,11 @@ where
     ) -> List<DB, EntityId, T, F, C, M, fn(Entity<EntityId, T>) -> M> {
         List {
             pool: self.pool,
+            notifier: self.notifier,
             _data: PhantomData,
+            filters: self.filters,
             condition: self.condition,
+            limit: self.limit,
             map,
         }
     }
@@ -204,6 +797,59 @@ where
     }
 }
 
+impl<DB, EntityId, T, F, C> List<DB, EntityId, T, F, C>
+where
+    DB: Database,
+{
+    /// Include only entities whose reference `column` points to an entity containing the
+    /// components `R` and matching the filters applied by `join`, returning these
+    /// components along with each entity.
+    ///
+    /// ```rust
+    /// # use erm::prelude::*;
+    /// # #[derive(Component)]
+    /// # struct Item;
+    /// # #[derive(Component)]
+    /// # struct Name(String);
+    /// # #[derive(Component)]
+    /// # struct Player;
+    /// #[derive(Component)]
+    /// struct Owner(EntityRef<i64>);
+    ///
+    /// # async fn example(backend: SqliteBackend<i64>) {
+    /// // Items owned by players, along with the name of their owner.
+    /// let items = backend
+    ///     .list::<(Item, Owner)>()
+    ///     .fetch_ref::<Name, _>(Owner::FIELDS.self_0, |owner| owner.with::<Player>());
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn fetch_ref<R, J>(
+        self,
+        column: ReflectedColumn<EntityRef<EntityId>>,
+        join: impl FnOnce(Referenced) -> Referenced<J>,
+    ) -> List<DB, EntityId, Dereferenced<T, R>, (F, FetchRef<R, J>), C> {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
+
+        List {
+            pool: self.pool,
+            notifier: self.notifier,
+            _data: PhantomData,
+            // Applied after the other filters, so the columns of each fetched reference
+            // follow those of the references fetched before it.
+            filters: (
+                self.filters,
+                FetchRef::new(column.column_name(), join(Referenced::new()).into_filter()),
+            ),
+            condition: self.condition,
+            limit: self.limit,
+            map: identity::<EntityId, Dereferenced<T, R>>,
+        }
+    }
+}
+
 impl<DB, EntityId, T, F, Cond, Out, Map: Fn(Entity<EntityId, T>) -> Out>
     List<DB, EntityId, T, F, Cond, Out, Map>
 where
@@ -219,9 +865,7 @@ where
     /// Execute the query, returning a stream of results.
     pub fn fetch(self) -> impl Stream<Item = Result<Out, sqlx::Error>> {
         stream! {
-            let mut sql = crate::cte::serialize(<F as Filter<DB>>::cte(<T as Deserializeable<DB>>::cte()).as_ref()).unwrap();
-            sql.push_str(" where ");
-            self.condition.serialize(&mut sql).unwrap();
+            let sql = self.sql();
 
             let query = self.condition.bind(sqlx::query_as::<DB, Entity<EntityId, T>>(&sql));
 
@@ -233,4 +877,388 @@ where
             }
         }
     }
+
+    /// Execute the query on the given connection, such as an open transaction,
+    /// returning a stream of results.
+    pub fn fetch_in<'c>(
+        self,
+        connection: &'c mut <DB as sqlx::Database>::Connection,
+    ) -> impl Stream<Item = Result<Out, sqlx::Error>> + 'c
+    where
+        Self: 'c,
+    {
+        stream! {
+            let sql = self.sql();
+
+            let query = self.condition.bind(sqlx::query_as::<DB, Entity<EntityId, T>>(&sql));
+
+            for await result in query.fetch(connection) {
+                yield match result {
+                    Ok(result) => Ok((self.map)(result)),
+                    Err(err) => Err(err)
+                }
+            }
+        }
+    }
+
+    fn sql(&self) -> String {
+        let cte = self.filters.cte(<T as Deserializeable<DB>>::cte());
+
+        let mut sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        sql.push_str(" where ");
+        self.condition.serialize(&mut sql).unwrap();
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" limit {limit}"));
+        }
+
+        sql.push_str(&crate::cte::lock_clause(cte.as_ref()).unwrap());
+        sql
+    }
+}
+
+impl<DB, EntityId, T, F, Cond, Out, Map> List<DB, EntityId, T, F, Cond, Out, Map>
+where
+    DB: Database + Notify + DatabasePlaceholder,
+    T: Deserializeable<DB> + Unpin + Send,
+    F: Filter<DB>,
+    Cond: for<'c> Condition<'c, DB> + Clone,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'e> EntityId: sqlx::Decode<'e, DB>
+        + sqlx::Encode<'e, DB>
+        + sqlx::Type<DB>
+        + Clone
+        + Hash
+        + Eq
+        + Unpin
+        + Send
+        + 'static,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    /// Subscribe to changes made to entities matching the query.
+    ///
+    /// The query is re-evaluated against the current state of the entity whenever a
+    /// notification arrives, so a change which is quickly followed by another may only
+    /// be observed in its final state.
+    ///
+    /// The subscription keeps track of the entities matching the query, starting with the
+    /// ones matching it when the returned future resolves, and only reports an entity as
+    /// removed if it matched the query before the change. Should notifications be lost,
+    /// all matching entities are reported again.
+    ///
+    /// The triggers producing notifications are created on the tables read by the query,
+    /// unless they exist already.
+    ///
+    /// See [`crate::subscription`] for details.
+    pub async fn subscribe(
+        self,
+    ) -> Result<impl Stream<Item = Result<Event<EntityId, T>, sqlx::Error>>, sqlx::Error> {
+        let cte = self.filters.cte(<T as Deserializeable<DB>>::cte());
+
+        let mut tables = Vec::new();
+        crate::subscription::tables(cte.as_ref(), &mut tables);
+
+        let mut entity_column = String::from("__cte_");
+        cte.table_name(&mut entity_column).unwrap();
+        entity_column.push_str("__entity");
+
+        let mut members_sql =
+            crate::cte::serialize_projection(cte.as_ref(), &entity_column).unwrap();
+        members_sql.push_str(" where ");
+        self.condition.serialize(&mut members_sql).unwrap();
+
+        let mut matching_sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        matching_sql.push_str(" where ");
+        self.condition.serialize(&mut matching_sql).unwrap();
+
+        let mut sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        sql.push_str(" where ");
+        sql.push_str(&entity_column);
+        sql.push_str(" = ");
+        sql.push_str(&<DB as DatabasePlaceholder>::placeholder(1));
+        sql.push_str(" and ");
+        self.condition.serialize(&mut sql).unwrap();
+
+        for table in &tables {
+            <DB as Notify>::create_triggers::<EntityId>(&self.pool, table).await?;
+        }
+
+        let notifications =
+            <DB as Notify>::notifications::<EntityId>(self.pool.clone(), self.notifier.clone())
+                .await?;
+
+        // Entities changed while this runs are re-evaluated once their notification arrives.
+        let mut members: HashSet<EntityId> = self
+            .condition
+            .clone()
+            .bind(sqlx::query_as::<DB, (EntityId,)>(&members_sql))
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(entity,)| entity)
+            .collect();
+
+        Ok(stream! {
+            for await notification in notifications {
+                let notification = match notification {
+                    Ok(Received::Notification(notification)) => notification,
+                    Ok(Received::Missed) => {
+                        // Any member may have changed, so compare all matching entities against them.
+                        let query = self
+                            .condition
+                            .clone()
+                            .bind(sqlx::query_as::<DB, Entity<EntityId, T>>(&matching_sql));
+
+                        let entities = match query.fetch_all(&self.pool).await {
+                            Ok(entities) => entities,
+                            Err(err) => {
+                                yield Err(err);
+                                continue;
+                            }
+                        };
+
+                        let previous = std::mem::take(&mut members);
+                        for entity in entities {
+                            members.insert(entity.id().clone());
+
+                            if previous.contains(entity.id()) {
+                                yield Ok(Event::Updated(entity))
+                            } else {
+                                yield Ok(Event::Inserted(entity))
+                            }
+                        }
+
+                        for entity in previous {
+                            if !members.contains(&entity) {
+                                yield Ok(Event::Removed(entity))
+                            }
+                        }
+
+                        continue;
+                    }
+                    Err(err) => {
+                        yield Err(err);
+                        continue;
+                    }
+                };
+
+                if !tables.iter().any(|table| table.eq_ignore_ascii_case(&notification.table)) {
+                    continue;
+                }
+
+                // Any change, including a removal, may make an entity start or stop
+                // matching the query, for example through `without`.
+                let query = self.condition.clone().bind(
+                    sqlx::query_as::<DB, Entity<EntityId, T>>(&sql).bind(&notification.entity),
+                );
+
+                match query.fetch_optional(&self.pool).await {
+                    Ok(Some(entity)) => {
+                        if members.insert(notification.entity) {
+                            yield Ok(Event::Inserted(entity))
+                        } else {
+                            yield Ok(Event::Updated(entity))
+                        }
+                    }
+                    Ok(None) if members.remove(&notification.entity) => {
+                        yield Ok(Event::Removed(notification.entity))
+                    }
+                    Ok(None) => continue,
+                    Err(err) => yield Err(err),
+                }
+            }
+        })
+    }
+}
+
+impl<DB, EntityId, T, F, Cond, Out, Map> List<DB, EntityId, T, F, Cond, Out, Map>
+where
+    DB: Database,
+    T: Deserializeable<DB>,
+    F: Filter<DB>,
+    Cond: for<'c> Condition<'c, DB>,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    /// Compute the given [`Aggregate`] over all matching entities.
+    ///
+    /// See [`crate::aggregate`] for the available aggregates.
+    pub async fn aggregate<A: Aggregate<DB>>(self, aggregate: A) -> Result<A::Output, sqlx::Error> {
+        let mut projection = String::new();
+        aggregate.serialize(&mut projection).unwrap();
+
+        let mut sql = crate::cte::serialize_projection(
+            self.filters.cte(<T as Deserializeable<DB>>::cte()).as_ref(),
+            &projection,
+        )
+        .unwrap();
+        sql.push_str(" where ");
+        self.condition.serialize(&mut sql).unwrap();
+
+        let query = self
+            .condition
+            .bind(sqlx::query_as::<DB, (A::Output,)>(&sql));
+
+        Ok(query.fetch_one(&self.pool).await?.0)
+    }
+
+    /// Sum the values of `column` across all matching entities.
+    pub async fn sum<V>(self, column: ReflectedColumn<V>) -> Result<Option<V>, sqlx::Error>
+    where
+        Sum<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Sum::new(column)).await
+    }
+
+    /// Average the values of `column` across all matching entities.
+    pub async fn avg<V>(self, column: ReflectedColumn<V>) -> Result<Option<f64>, sqlx::Error>
+    where
+        Avg<V>: Aggregate<DB, Output = Option<f64>>,
+    {
+        self.aggregate(Avg::new(column)).await
+    }
+
+    /// Find the smallest value of `column` across all matching entities.
+    pub async fn min<V>(self, column: ReflectedColumn<V>) -> Result<Option<V>, sqlx::Error>
+    where
+        Min<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Min::new(column)).await
+    }
+
+    /// Find the largest value of `column` across all matching entities.
+    pub async fn max<V>(self, column: ReflectedColumn<V>) -> Result<Option<V>, sqlx::Error>
+    where
+        Max<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Max::new(column)).await
+    }
+
+    /// Count the number of matching entities.
+    pub async fn count(self) -> Result<i64, sqlx::Error>
+    where
+        Count: Aggregate<DB, Output = i64>,
+    {
+        self.aggregate(Count).await
+    }
+
+    /// Group the matching entities by the value of `column`, computing
+    /// aggregates for each group rather than across all entities.
+    pub fn group_by<Key>(self, column: ReflectedColumn<Key>) -> GroupBy<DB, T, F, Cond, Key> {
+        GroupBy {
+            pool: self.pool,
+            _data: PhantomData,
+            filters: self.filters,
+            condition: self.condition,
+            key: column.column_name(),
+        }
+    }
+}
+
+/// Query grouping entities by the value of a column.
+///
+/// Each aggregate yields a stream of `(key, aggregate)` pairs, one for each distinct key.
+pub struct GroupBy<DB, T, F, C, Key>
+where
+    DB: Database,
+{
+    pool: Pool<DB>,
+    _data: PhantomData<(T, Key)>,
+    filters: F,
+    condition: C,
+    key: &'static str,
+}
+
+impl<DB, T, F, Cond, Key> GroupBy<DB, T, F, Cond, Key>
+where
+    DB: Database,
+    T: Deserializeable<DB>,
+    F: Filter<DB>,
+    Cond: for<'c> Condition<'c, DB>,
+    Key: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+    for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    /// Compute the given [`Aggregate`] for each group.
+    pub fn aggregate<A: Aggregate<DB>>(
+        self,
+        aggregate: A,
+    ) -> impl Stream<Item = Result<(Key, A::Output), sqlx::Error>> {
+        stream! {
+            let mut projection = String::from(self.key);
+            projection.push_str(", ");
+            aggregate.serialize(&mut projection).unwrap();
+
+            let mut sql = crate::cte::serialize_projection(
+                self.filters.cte(<T as Deserializeable<DB>>::cte()).as_ref(),
+                &projection,
+            )
+            .unwrap();
+            sql.push_str(" where ");
+            self.condition.serialize(&mut sql).unwrap();
+            sql.push_str(" group by ");
+            sql.push_str(self.key);
+
+            let query = self.condition.bind(sqlx::query_as::<DB, (Key, A::Output)>(&sql));
+
+            for await result in query.fetch(&self.pool) {
+                yield result;
+            }
+        }
+    }
+
+    /// Sum the values of `column` within each group.
+    pub fn sum<V>(
+        self,
+        column: ReflectedColumn<V>,
+    ) -> impl Stream<Item = Result<(Key, Option<V>), sqlx::Error>>
+    where
+        Sum<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Sum::new(column))
+    }
+
+    /// Average the values of `column` within each group.
+    pub fn avg<V>(
+        self,
+        column: ReflectedColumn<V>,
+    ) -> impl Stream<Item = Result<(Key, Option<f64>), sqlx::Error>>
+    where
+        Avg<V>: Aggregate<DB, Output = Option<f64>>,
+    {
+        self.aggregate(Avg::new(column))
+    }
+
+    /// Find the smallest value of `column` within each group.
+    pub fn min<V>(
+        self,
+        column: ReflectedColumn<V>,
+    ) -> impl Stream<Item = Result<(Key, Option<V>), sqlx::Error>>
+    where
+        Min<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Min::new(column))
+    }
+
+    /// Find the largest value of `column` within each group.
+    pub fn max<V>(
+        self,
+        column: ReflectedColumn<V>,
+    ) -> impl Stream<Item = Result<(Key, Option<V>), sqlx::Error>>
+    where
+        Max<V>: Aggregate<DB, Output = Option<V>>,
+    {
+        self.aggregate(Max::new(column))
+    }
+
+    /// Count the number of entities within each group.
+    pub fn count(self) -> impl Stream<Item = Result<(Key, i64), sqlx::Error>>
+    where
+        Count: Aggregate<DB, Output = i64>,
+    {
+        self.aggregate(Count)
+    }
 }
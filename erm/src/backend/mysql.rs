@@ -1,17 +1,25 @@
 use std::{future::Future, marker::PhantomData};
 
+use futures::Stream;
 use sqlx::mysql::MySqlQueryResult;
 use sqlx::{MySql, Pool};
 
+use crate::changelog::{Change, Changelog};
+use crate::component::{SoftDelete, Versioned};
+use crate::entity::EntityRef;
 use crate::prelude::{Component, Deserializeable, Serializable};
-use crate::row::Rowed;
+use crate::reflect::ReflectedColumn;
+use crate::row::{Entity, Related};
+use crate::subscription::Notifier;
 use crate::tables::Removable;
-use crate::{archetype::Archetype, condition::All};
+use crate::version::{RowsAffected, UpdateError, Version};
+use crate::{archetype::Archetype, condition::All, cte::Direction};
 
-use super::{Backend, List};
+use super::{changes, despawn, restore, traverse, update_versioned, Backend, List, Registry};
 
 pub struct MySqlBackend<EntityId> {
     pool: Pool<MySql>,
+    components: Registry<MySql, EntityId>,
     _entity: PhantomData<EntityId>,
 }
 
@@ -19,6 +27,7 @@ impl<EntityId> MySqlBackend<EntityId> {
     pub fn new(pool: Pool<MySql>) -> Self {
         MySqlBackend {
             pool,
+            components: Registry::default(),
             _entity: PhantomData,
         }
     }
@@ -34,65 +43,150 @@ where
         + 'static,
     for<'entity> &'entity EntityId: Send,
 {
-    fn register<T>(&self) -> impl Future<Output = Result<MySqlQueryResult, sqlx::Error>>
+    async fn register<T>(&self) -> Result<MySqlQueryResult, sqlx::Error>
     where
         T: Component<MySql>,
     {
-        <T as Component<MySql>>::create_component_table::<EntityId>(&self.pool)
+        self.components.register::<T>();
+
+        if <T as Component<MySql>>::CHANGELOG {
+            crate::changelog::create_table::<MySql, EntityId>(&self.pool).await?;
+        }
+
+        <T as Component<MySql>>::create_component_table::<EntityId>(&self.pool).await
     }
 
     fn list<T>(&self) -> List<MySql, EntityId, T, (), All> {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
         List {
             pool: self.pool.clone(),
+            notifier: Notifier::default(),
             _data: PhantomData,
+            filters: (),
             condition: All,
+            limit: None,
+            map: identity::<EntityId, T>,
         }
     }
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    async fn get<T>(&self, entity: &EntityId) -> Result<T, sqlx::Error>
     where
         T: Deserializeable<MySql> + Unpin + Send + 'static,
     {
-        async move {
-            let sql = crate::cte::serialize(<T as Deserializeable<MySql>>::cte().as_ref()).unwrap();
+        let cte = <T as Deserializeable<MySql>>::cte();
+        let mut sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        sql.push_str(" where __cte_");
+        cte.table_name(&mut sql).unwrap();
+        sql.push_str("__entity = ?");
 
-            let result: Rowed<EntityId, T> = sqlx::query_as(&sql)
-                .bind(entity)
-                .fetch_one(&self.pool)
-                .await?;
+        let result: Entity<EntityId, T> = sqlx::query_as(&sql)
+            .bind(entity)
+            .fetch_one(&self.pool)
+            .await?;
 
-            Ok(result.inner)
-        }
+        Ok(result.into_components())
+    }
+
+    fn descendants_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<MySql>,
+        T: Deserializeable<MySql> + Unpin + Send + 'static,
+    {
+        traverse::<MySql, EntityId, P, T>(&self.pool, parent, root, Direction::Descendants)
+    }
+
+    fn ancestors_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<MySql>,
+        T: Deserializeable<MySql> + Unpin + Send + 'static,
+    {
+        traverse::<MySql, EntityId, P, T>(&self.pool, parent, root, Direction::Ancestors)
     }
 
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
-        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
+        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<MySql>>::insert(&components, &self.pool, entity)
+        <T as Archetype<MySql>>::insert(components, &self.pool, entity)
     }
 
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + Sync + 'static,
+    {
+        <T as Archetype<MySql>>::update(components, &self.pool, entity)
+    }
+
+    fn update_versioned<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        component: &'a Version<T>,
+    ) -> impl Future<Output = Result<i64, UpdateError>> + 'a
     where
-        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
+        MySql: RowsAffected,
+        T: Versioned<MySql> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<MySql>>::update(&components, &self.pool, entity)
+        update_versioned::<MySql, EntityId, T>(&self.pool, entity, component)
     }
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    fn remove<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
         T: Archetype<MySql> + Removable<MySql> + Unpin + Send + 'static,
     {
         <T as Archetype<MySql>>::remove(&self.pool, entity)
     }
+
+    fn changelog<'a, T>(
+        &'a self,
+        after: i64,
+    ) -> impl Stream<Item = Result<Change<EntityId, T>, sqlx::Error>> + 'a
+    where
+        MySql: Changelog,
+        T: Component<MySql> + Unpin + Send + 'static,
+    {
+        changes::<MySql, EntityId, T>(&self.pool, after)
+    }
+
+    fn restore<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        T: SoftDelete<MySql> + Unpin + Send + 'static,
+    {
+        restore::<MySql, EntityId, T>(&self.pool, entity)
+    }
+
+    fn despawn<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        EntityId: PartialEq,
+    {
+        despawn::<MySql, EntityId>(&self.pool, &self.components, entity)
+    }
 }
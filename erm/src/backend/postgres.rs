@@ -1,18 +1,29 @@
 use std::{future::Future, marker::PhantomData};
 
-use sqlx::postgres::PgQueryResult;
-use sqlx::{Pool, Postgres};
+use async_stream::stream;
+use futures::Stream;
+use sqlx::postgres::{PgListener, PgQueryResult};
+use sqlx::{Executor as _, Pool, Postgres, TypeInfo as _};
 
 use crate::archetype::Archetype;
+use crate::changelog::{Change, Changelog};
+use crate::component::{SoftDelete, Versioned};
 use crate::condition::All;
+use crate::cte::Direction;
+use crate::entity::EntityRef;
 use crate::prelude::{Component, Deserializeable, Serializable};
-use crate::row::Rowed;
+use crate::reflect::ReflectedColumn;
+use crate::row::{Entity, Related};
+use crate::subscription::{Notification, Notifier, Notify, Operation, Received};
 use crate::tables::Removable;
+use crate::version::{RowsAffected, UpdateError, Version};
 
-use super::{Backend, List};
+use super::{changes, despawn, restore, traverse, update_versioned, Backend, List, Registry};
 
 pub struct PostgresBackend<EntityId> {
     pool: Pool<Postgres>,
+    components: Registry<Postgres, EntityId>,
+    notifier: Notifier,
     _entity: PhantomData<EntityId>,
 }
 
@@ -20,11 +31,105 @@ impl<EntityId> PostgresBackend<EntityId> {
     pub fn new(pool: Pool<Postgres>) -> Self {
         PostgresBackend {
             pool,
+            components: Registry::default(),
+            notifier: Notifier::default(),
             _entity: PhantomData,
         }
     }
 }
 
+impl Notify for Postgres {
+    async fn create_triggers<EntityId>(pool: &Pool<Self>, table: &str) -> Result<(), sqlx::Error>
+    where
+        EntityId: sqlx::Type<Self>,
+    {
+        pool.execute(
+            "create or replace function erm_notify() returns trigger as $$\n\
+            begin\n  \
+              if tg_op = 'DELETE' then\n    \
+                perform pg_notify('erm', 'delete:' || tg_table_name || ':' || old.entity::text);\n  \
+              else\n    \
+                perform pg_notify('erm', lower(tg_op) || ':' || tg_table_name || ':' || new.entity::text);\n  \
+              end if;\n  \
+              return null;\n\
+            end;\n\
+            $$ language plpgsql;",
+        )
+        .await?;
+
+        // `create or replace trigger` requires Postgres 14, so tolerate the trigger
+        // existing already, which also covers concurrent subscriptions creating it.
+        let sql = format!(
+            "do $$ begin\n  \
+              create trigger {table}_erm_notify after insert or update or delete on {table}\n    \
+                for each row execute procedure erm_notify();\n\
+            exception when duplicate_object then null;\n\
+            end $$;"
+        );
+
+        pool.execute(sql.as_str()).await?;
+        Ok(())
+    }
+
+    async fn notifications<EntityId>(
+        pool: Pool<Self>,
+        _: Notifier,
+    ) -> Result<impl Stream<Item = Result<Received<EntityId>, sqlx::Error>>, sqlx::Error>
+    where
+        EntityId: for<'r> sqlx::Decode<'r, Self> + sqlx::Type<Self> + Unpin + Send + 'static,
+    {
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen("erm").await?;
+
+        // Payloads carry the entity ID as text, so let the database convert it back.
+        let cast = format!(
+            "select cast($1 as {})",
+            <EntityId as sqlx::Type<Postgres>>::type_info().name()
+        );
+
+        Ok(stream! {
+            loop {
+                // Notifications sent while the listener reconnects are lost.
+                let notification = match listener.try_recv().await {
+                    Ok(Some(notification)) => notification,
+                    Ok(None) => {
+                        yield Ok(Received::Missed);
+                        continue;
+                    }
+                    Err(err) => {
+                        yield Err(err);
+                        continue;
+                    }
+                };
+
+                let mut payload = notification.payload().splitn(3, ':');
+                let (Some(operation), Some(table), Some(entity)) =
+                    (payload.next(), payload.next(), payload.next())
+                else {
+                    continue;
+                };
+
+                let Some(operation) = Operation::parse(operation) else {
+                    continue;
+                };
+
+                match sqlx::query_as::<Postgres, (EntityId,)>(&cast)
+                    .bind(entity)
+                    .fetch_one(&pool)
+                    .await
+                {
+                    Ok((entity,)) => yield Ok(Received::Notification(Notification {
+                        operation,
+                        table: table.to_string(),
+                        entity,
+                    })),
+                    Err(err) => yield Err(err),
+                }
+            }
+        })
+    }
+}
+
 impl<EntityId> Backend<Postgres, EntityId> for PostgresBackend<EntityId>
 where
     EntityId: for<'q> sqlx::Encode<'q, Postgres>
@@ -35,66 +140,150 @@ where
         + 'static,
     for<'entity> &'entity EntityId: Send,
 {
-    fn register<T>(&self) -> impl Future<Output = Result<PgQueryResult, sqlx::Error>>
+    async fn register<T>(&self) -> Result<PgQueryResult, sqlx::Error>
     where
         T: Component<Postgres>,
     {
-        <T as Component<Postgres>>::create_component_table::<EntityId>(&self.pool)
+        self.components.register::<T>();
+
+        if <T as Component<Postgres>>::CHANGELOG {
+            crate::changelog::create_table::<Postgres, EntityId>(&self.pool).await?;
+        }
+
+        <T as Component<Postgres>>::create_component_table::<EntityId>(&self.pool).await
     }
 
     fn list<T>(&self) -> List<Postgres, EntityId, T, (), All> {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
         List {
             pool: self.pool.clone(),
+            notifier: self.notifier.clone(),
             _data: PhantomData,
+            filters: (),
             condition: All,
+            limit: None,
+            map: identity::<EntityId, T>,
         }
     }
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    async fn get<T>(&self, entity: &EntityId) -> Result<T, sqlx::Error>
     where
         T: Deserializeable<Postgres> + Unpin + Send + 'static,
     {
-        async move {
-            let sql =
-                crate::cte::serialize(<T as Deserializeable<Postgres>>::cte().as_ref()).unwrap();
+        let cte = <T as Deserializeable<Postgres>>::cte();
+        let mut sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        sql.push_str(" where __cte_");
+        cte.table_name(&mut sql).unwrap();
+        sql.push_str("__entity = $1");
 
-            let result: Rowed<EntityId, T> = sqlx::query_as(&sql)
-                .bind(entity)
-                .fetch_one(&self.pool)
-                .await?;
+        let result: Entity<EntityId, T> = sqlx::query_as(&sql)
+            .bind(entity)
+            .fetch_one(&self.pool)
+            .await?;
 
-            Ok(result.inner)
-        }
+        Ok(result.into_components())
+    }
+
+    fn descendants_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<Postgres>,
+        T: Deserializeable<Postgres> + Unpin + Send + 'static,
+    {
+        traverse::<Postgres, EntityId, P, T>(&self.pool, parent, root, Direction::Descendants)
+    }
+
+    fn ancestors_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<Postgres>,
+        T: Deserializeable<Postgres> + Unpin + Send + 'static,
+    {
+        traverse::<Postgres, EntityId, P, T>(&self.pool, parent, root, Direction::Ancestors)
     }
 
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
-        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
+        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<Postgres>>::insert(&components, &self.pool, entity)
+        <T as Archetype<Postgres>>::insert(components, &self.pool, entity)
     }
 
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
-        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
+        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<Postgres>>::update(&components, &self.pool, entity)
+        <T as Archetype<Postgres>>::update(components, &self.pool, entity)
     }
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    fn update_versioned<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        component: &'a Version<T>,
+    ) -> impl Future<Output = Result<i64, UpdateError>> + 'a
+    where
+        Postgres: RowsAffected,
+        T: Versioned<Postgres> + Unpin + Send + Sync + 'static,
+    {
+        update_versioned::<Postgres, EntityId, T>(&self.pool, entity, component)
+    }
+
+    fn remove<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
         T: Archetype<Postgres> + Removable<Postgres> + Unpin + Send + 'static,
     {
         <T as Archetype<Postgres>>::remove(&self.pool, entity)
     }
+
+    fn changelog<'a, T>(
+        &'a self,
+        after: i64,
+    ) -> impl Stream<Item = Result<Change<EntityId, T>, sqlx::Error>> + 'a
+    where
+        Postgres: Changelog,
+        T: Component<Postgres> + Unpin + Send + 'static,
+    {
+        changes::<Postgres, EntityId, T>(&self.pool, after)
+    }
+
+    fn restore<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        T: SoftDelete<Postgres> + Unpin + Send + 'static,
+    {
+        restore::<Postgres, EntityId, T>(&self.pool, entity)
+    }
+
+    fn despawn<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        EntityId: PartialEq,
+    {
+        despawn::<Postgres, EntityId>(&self.pool, &self.components, entity)
+    }
 }
@@ -1,42 +1,185 @@
 use std::{future::Future, marker::PhantomData};
 
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteQueryResult};
-use sqlx::{Pool, Sqlite};
+use async_stream::stream;
+use futures::{Stream, StreamExt as _};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteOperation, SqlitePoolOptions, SqliteQueryResult};
+use sqlx::{Executor as _, Pool, Sqlite, TypeInfo as _};
 
 use crate::archetype::Archetype;
+use crate::changelog::{Change, Changelog};
+use crate::component::{SoftDelete, Versioned};
 use crate::condition::All;
+use crate::cte::Direction;
+use crate::entity::EntityRef;
 use crate::prelude::{Component, Deserializeable, Serializable};
-use crate::row::Entity;
+use crate::reflect::ReflectedColumn;
+use crate::row::{Entity, Related};
+use crate::subscription::{Notification, Notifier, Notify, Operation, Received};
 use crate::tables::Removable;
+use crate::version::{RowsAffected, UpdateError, Version};
 
-use super::{Backend, List};
+use super::{changes, despawn, restore, traverse, update_versioned, Backend, List, Registry};
+
+/// Number of change events kept in the `erm_events` table for subscriptions to catch up on.
+const EVENT_HISTORY: usize = 1024;
 
 pub struct SqliteBackend<EntityId> {
     pool: Pool<Sqlite>,
+    components: Registry<Sqlite, EntityId>,
+    notifier: Notifier,
     _entity: PhantomData<EntityId>,
 }
 
 impl<EntityId> SqliteBackend<EntityId> {
+    /// Construct a backend from an existing pool.
+    ///
+    /// Subscriptions require an update hook on each connection of the pool, so
+    /// use [`SqliteBackend::connect_with`] if you intend to use [`List::subscribe`].
     pub fn new(pool: Pool<Sqlite>) -> Self {
         SqliteBackend {
             pool,
+            components: Registry::default(),
+            notifier: Notifier::default(),
             _entity: PhantomData,
         }
     }
 
+    /// Connect to the database, installing the update hook used by subscriptions
+    /// on every connection in the pool.
+    pub async fn connect_with(
+        options: SqliteConnectOptions,
+        pool_options: SqlitePoolOptions,
+    ) -> Result<Self, sqlx::Error> {
+        let notifier = Notifier::default();
+
+        let hook = notifier.clone();
+        let pool = pool_options
+            .after_connect(move |connection, _| {
+                let notifier = hook.clone();
+                Box::pin(async move {
+                    connection
+                        .lock_handle()
+                        .await?
+                        .set_update_hook(move |update| {
+                            if update.table == "erm_events"
+                                && matches!(update.operation, SqliteOperation::Insert)
+                            {
+                                notifier.notify();
+                            }
+                        });
+
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await?;
+
+        Ok(SqliteBackend {
+            pool,
+            components: Registry::default(),
+            notifier,
+            _entity: PhantomData,
+        })
+    }
+
     pub async fn in_memory() -> Self {
         let options = SqliteConnectOptions::new().in_memory(true);
 
-        let pool = SqlitePoolOptions::new()
+        let pool_options = SqlitePoolOptions::new()
             .min_connections(1)
             .max_connections(1)
             .idle_timeout(None)
-            .max_lifetime(None)
-            .connect_with(options)
-            .await
-            .unwrap();
+            .max_lifetime(None);
 
-        Self::new(pool)
+        Self::connect_with(options, pool_options).await.unwrap()
+    }
+}
+
+impl Notify for Sqlite {
+    async fn create_triggers<EntityId>(pool: &Pool<Self>, table: &str) -> Result<(), sqlx::Error>
+    where
+        EntityId: sqlx::Type<Self>,
+    {
+        let entity = <EntityId as sqlx::Type<Sqlite>>::type_info()
+            .name()
+            .to_string();
+
+        let mut sql = format!(
+            "create table if not exists erm_events(\n  component text not null,\n  operation text not null,\n  entity {entity} not null\n);\n\
+            create trigger if not exists erm_events_prune after insert on erm_events begin\n  delete from erm_events where rowid <= new.rowid - {EVENT_HISTORY};\nend;\n"
+        );
+
+        for (operation, row) in [("insert", "new"), ("update", "new"), ("delete", "old")] {
+            sql.push_str(&format!(
+                "create trigger if not exists {table}_erm_{operation} after {operation} on {table} begin\n  \
+                insert into erm_events(component, operation, entity) values('{table}', '{operation}', {row}.entity);\nend;\n"
+            ));
+        }
+
+        pool.execute(sql.as_str()).await?;
+        Ok(())
+    }
+
+    async fn notifications<EntityId>(
+        pool: Pool<Self>,
+        notifier: Notifier,
+    ) -> Result<impl Stream<Item = Result<Received<EntityId>, sqlx::Error>>, sqlx::Error>
+    where
+        EntityId: for<'r> sqlx::Decode<'r, Self> + sqlx::Type<Self> + Unpin + Send + 'static,
+    {
+        let mut wakeups = notifier.subscribe();
+
+        // Only events which happen after the subscription was created are of interest.
+        let (mut last,) =
+            sqlx::query_as::<Sqlite, (i64,)>("select coalesce(max(rowid), 0) from erm_events")
+                .fetch_one(&pool)
+                .await?;
+
+        Ok(stream! {
+            while wakeups.next().await.is_some() {
+                // The hook fires while the change is being made, so wait for the writing
+                // transaction to finish by taking the write lock, before catching up on
+                // every event we haven't seen yet.
+                let events = async {
+                    let mut connection = pool.acquire().await?;
+                    connection.execute("begin immediate").await?;
+
+                    let events = sqlx::query_as::<Sqlite, (i64, String, String, EntityId)>(
+                        "select rowid, component, operation, entity from erm_events where rowid > ? order by rowid",
+                    )
+                    .bind(last)
+                    .fetch_all(&mut *connection)
+                    .await;
+
+                    connection.execute("rollback").await?;
+                    events
+                }
+                .await;
+
+                match events {
+                    // Rowids are handed out in sequence, so a gap means events were pruned
+                    // before we caught up on them. Those which remain are covered by
+                    // evaluating the query again.
+                    Ok(events) if events.first().is_some_and(|(rowid, ..)| *rowid > last + 1) => {
+                        if let Some((rowid, ..)) = events.last() {
+                            last = *rowid;
+                        }
+
+                        yield Ok(Received::Missed);
+                    }
+                    Ok(events) => {
+                        for (rowid, table, operation, entity) in events {
+                            last = rowid;
+
+                            if let Some(operation) = Operation::parse(&operation) {
+                                yield Ok(Received::Notification(Notification { operation, table, entity }));
+                            }
+                        }
+                    }
+                    Err(err) => yield Err(err),
+                }
+            }
+        })
     }
 }
 
@@ -50,11 +193,17 @@ where
         + 'static,
     for<'entity> &'entity EntityId: Send,
 {
-    fn register<T>(&self) -> impl Future<Output = Result<SqliteQueryResult, sqlx::Error>>
+    async fn register<T>(&self) -> Result<SqliteQueryResult, sqlx::Error>
     where
         T: Component<Sqlite>,
     {
-        <T as Component<Sqlite>>::create_component_table::<EntityId>(&self.pool)
+        self.components.register::<T>();
+
+        if <T as Component<Sqlite>>::CHANGELOG {
+            crate::changelog::create_table::<Sqlite, EntityId>(&self.pool).await?;
+        }
+
+        <T as Component<Sqlite>>::create_component_table::<EntityId>(&self.pool).await
     }
 
     fn list<T>(&self) -> List<Sqlite, EntityId, T, (), All> {
@@ -63,57 +212,131 @@ where
         }
         List {
             pool: self.pool.clone(),
+            notifier: self.notifier.clone(),
             _data: PhantomData,
+            filters: (),
             condition: All,
+            limit: None,
             map: identity::<EntityId, T>,
         }
     }
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    async fn get<T>(&self, entity: &EntityId) -> Result<T, sqlx::Error>
     where
         T: Deserializeable<Sqlite> + Unpin + Send + 'static,
     {
-        async move {
-            let sql =
-                crate::cte::serialize(<T as Deserializeable<Sqlite>>::cte().as_ref()).unwrap();
+        let cte = <T as Deserializeable<Sqlite>>::cte();
+        let mut sql = crate::cte::serialize(cte.as_ref()).unwrap();
+        sql.push_str(" where __cte_");
+        cte.table_name(&mut sql).unwrap();
+        sql.push_str("__entity = ?");
 
-            let result: Entity<EntityId, T> = sqlx::query_as(&sql)
-                .bind(entity)
-                .fetch_one(&self.pool)
-                .await?;
+        let result: Entity<EntityId, T> = sqlx::query_as(&sql)
+            .bind(entity)
+            .fetch_one(&self.pool)
+            .await?;
 
-            Ok(result.into_components())
-        }
+        Ok(result.into_components())
+    }
+
+    fn descendants_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<Sqlite>,
+        T: Deserializeable<Sqlite> + Unpin + Send + 'static,
+    {
+        traverse::<Sqlite, EntityId, P, T>(&self.pool, parent, root, Direction::Descendants)
+    }
+
+    fn ancestors_of<'a, P, T>(
+        &'a self,
+        parent: ReflectedColumn<EntityRef<EntityId>>,
+        root: &'a EntityId,
+    ) -> impl Stream<Item = Result<Related<EntityId, T>, sqlx::Error>> + 'a
+    where
+        P: Deserializeable<Sqlite>,
+        T: Deserializeable<Sqlite> + Unpin + Send + 'static,
+    {
+        traverse::<Sqlite, EntityId, P, T>(&self.pool, parent, root, Direction::Ancestors)
     }
 
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
-        T: Archetype<Sqlite> + Serializable<Sqlite> + Unpin + Send + 'static,
+        T: Archetype<Sqlite> + Serializable<Sqlite> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<Sqlite>>::insert(&components, &self.pool, entity)
+        <T as Archetype<Sqlite>>::insert(components, &self.pool, entity)
     }
 
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
-        T: Archetype<Sqlite> + Serializable<Sqlite> + Unpin + Send + 'static,
+        T: Archetype<Sqlite> + Serializable<Sqlite> + Unpin + Send + Sync + 'static,
     {
-        <T as Archetype<Sqlite>>::update(&components, &self.pool, entity)
+        <T as Archetype<Sqlite>>::update(components, &self.pool, entity)
     }
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    fn update_versioned<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        component: &'a Version<T>,
+    ) -> impl Future<Output = Result<i64, UpdateError>> + 'a
+    where
+        Sqlite: RowsAffected,
+        T: Versioned<Sqlite> + Unpin + Send + Sync + 'static,
+    {
+        update_versioned::<Sqlite, EntityId, T>(&self.pool, entity, component)
+    }
+
+    fn remove<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
     where
         T: Archetype<Sqlite> + Removable<Sqlite> + Unpin + Send + 'static,
     {
         <T as Archetype<Sqlite>>::remove(&self.pool, entity)
     }
+
+    fn changelog<'a, T>(
+        &'a self,
+        after: i64,
+    ) -> impl Stream<Item = Result<Change<EntityId, T>, sqlx::Error>> + 'a
+    where
+        Sqlite: Changelog,
+        T: Component<Sqlite> + Unpin + Send + 'static,
+    {
+        changes::<Sqlite, EntityId, T>(&self.pool, after)
+    }
+
+    fn restore<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        T: SoftDelete<Sqlite> + Unpin + Send + 'static,
+    {
+        restore::<Sqlite, EntityId, T>(&self.pool, entity)
+    }
+
+    fn despawn<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + 'a
+    where
+        EntityId: PartialEq,
+    {
+        despawn::<Sqlite, EntityId>(&self.pool, &self.components, entity)
+    }
 }
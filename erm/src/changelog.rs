@@ -0,0 +1,267 @@
+//! Opt-in record of every change made to a component.
+//!
+//! Components deriving `#[erm(changelog)]` append a row to the `erm_changelog` table
+//! for each insert, update and remove, within the same transaction as the change itself.
+//! The values of the component are stored as a JSON object, serialized by the database,
+//! and can be read back using [`Backend::changelog`](crate::backend::Backend::changelog).
+//! JSON has no binary type, so binary columns are stored hex-encoded on Sqlite and MySQL,
+//! and in the text form of `bytea` on Postgres.
+
+use sqlx::{prelude::FromRow, ColumnIndex, Database, Executor, Pool, Row, TypeInfo as _};
+
+use crate::{
+    archetype::DatabasePlaceholder,
+    component::{ColumnDefinition, Component},
+    row::OffsetRow,
+    serialization::Deserializeable,
+    subscription::Operation,
+};
+
+/// Database-specific SQL used for recording and reading the changelog.
+pub trait Changelog: Database + DatabasePlaceholder {
+    /// Expression evaluating to the current time in milliseconds since the unix epoch,
+    /// like the validity of versions in [`crate::history`].
+    const NOW: &'static str;
+
+    /// Statement creating the `erm_changelog` table, where `entity` is the sql type of entity IDs.
+    fn create_table(entity: &str) -> String;
+
+    /// Expression serializing the given columns of a row into a JSON object.
+    fn json_object(columns: &[ColumnDefinition<Self>]) -> String;
+
+    /// Expression extracting `column` from the JSON object stored in `data`,
+    /// in a form which can be decoded as `type_info`.
+    fn json_extract(column: &str, type_info: &Self::TypeInfo) -> String;
+}
+
+#[cfg(feature = "sqlite")]
+impl Changelog for sqlx::Sqlite {
+    const NOW: &'static str = "cast((julianday('now') - 2440587.5) * 86400000 as integer)";
+
+    fn create_table(entity: &str) -> String {
+        format!(
+            "create table if not exists erm_changelog(\n  sequence integer primary key autoincrement,\n  entity {entity} not null,\n  component text not null,\n  operation text not null,\n  recorded_at bigint not null,\n  data text not null\n);"
+        )
+    }
+
+    /// `hex` turns `null` into an empty string, which must not be decoded as an empty blob.
+    fn json_object(columns: &[ColumnDefinition<Self>]) -> String {
+        let pairs = columns
+            .iter()
+            .map(|column| match column.type_info().name() {
+                "BLOB" => format!(
+                    "'{name}', case when {name} is null then null else hex({name}) end",
+                    name = column.name()
+                ),
+                _ => format!("'{name}', {name}", name = column.name()),
+            })
+            .collect::<Vec<_>>();
+
+        format!("json_object({})", pairs.join(", "))
+    }
+
+    fn json_extract(column: &str, type_info: &Self::TypeInfo) -> String {
+        match type_info.name() {
+            "BLOB" => format!("unhex(json_extract(data, '$.{column}'))"),
+            _ => format!("json_extract(data, '$.{column}')"),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl Changelog for sqlx::Postgres {
+    const NOW: &'static str = "cast(extract(epoch from now()) * 1000 as bigint)";
+
+    fn create_table(entity: &str) -> String {
+        format!(
+            "create table if not exists erm_changelog(\n  sequence bigserial primary key,\n  entity {entity} not null,\n  component text not null,\n  operation text not null,\n  recorded_at bigint not null,\n  data text not null\n);"
+        )
+    }
+
+    fn json_object(columns: &[ColumnDefinition<Self>]) -> String {
+        let pairs = columns
+            .iter()
+            .map(|column| format!("'{name}', {name}", name = column.name()))
+            .collect::<Vec<_>>();
+
+        format!("cast(json_build_object({}) as text)", pairs.join(", "))
+    }
+
+    fn json_extract(column: &str, type_info: &Self::TypeInfo) -> String {
+        format!(
+            "cast(cast(data as json)->>'{column}' as {})",
+            type_info.name()
+        )
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl Changelog for sqlx::MySql {
+    const NOW: &'static str = "cast(unix_timestamp(now(3)) * 1000 as signed)";
+
+    fn create_table(entity: &str) -> String {
+        format!(
+            "create table if not exists erm_changelog(\n  sequence bigint auto_increment primary key,\n  entity {entity} not null,\n  component text not null,\n  operation text not null,\n  recorded_at bigint not null,\n  data json not null\n);"
+        )
+    }
+
+    fn json_object(columns: &[ColumnDefinition<Self>]) -> String {
+        let pairs = columns
+            .iter()
+            .map(|column| {
+                if is_binary(column.type_info()) {
+                    format!("'{name}', hex({name})", name = column.name())
+                } else {
+                    format!("'{name}', {name}", name = column.name())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        format!("json_object({})", pairs.join(", "))
+    }
+
+    /// MySQL can only extract JSON values as text, so columns must be decodable from text.
+    fn json_extract(column: &str, type_info: &Self::TypeInfo) -> String {
+        if is_binary(type_info) {
+            format!("unhex(json_unquote(json_extract(data, '$.{column}')))")
+        } else {
+            format!("json_unquote(json_extract(data, '$.{column}'))")
+        }
+    }
+}
+
+/// Whether values of the type are stored as binary strings, which MySQL would otherwise
+/// store in JSON as base64 with a type prefix.
+#[cfg(feature = "mysql")]
+fn is_binary(type_info: &<sqlx::MySql as Database>::TypeInfo) -> bool {
+    let name = type_info.name();
+    name.ends_with("BLOB") || name.ends_with("BINARY")
+}
+
+/// Statement recording the current values of the component `T` for an entity,
+/// whose ID is bound as the first parameter.
+///
+/// Removals must be recorded before the row is deleted.
+pub fn record<DB: Changelog, T: Component<DB>>(operation: Operation) -> String {
+    let table = <T as Component<DB>>::table();
+    let columns = <T as Component<DB>>::columns();
+
+    let operation = match operation {
+        Operation::Insert => "insert",
+        Operation::Update => "update",
+        Operation::Delete => "delete",
+    };
+
+    format!(
+        "insert into erm_changelog(entity, component, operation, recorded_at, data) select entity, '{table}', '{operation}', {now}, {data} from {table} where entity = {placeholder}",
+        now = <DB as Changelog>::NOW,
+        data = <DB as Changelog>::json_object(&columns),
+        placeholder = <DB as DatabasePlaceholder>::placeholder(1),
+    )
+}
+
+/// Query reading the changes made to the component `T` with a sequence number above
+/// the first parameter, in the order they were made.
+pub(crate) fn read<DB: Changelog, T: Component<DB>>() -> String {
+    let columns = <T as Component<DB>>::columns()
+        .iter()
+        .map(|column| <DB as Changelog>::json_extract(column.name(), column.type_info()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "select sequence, entity, operation, recorded_at, {columns} from erm_changelog where component = '{table}' and sequence > {placeholder} order by sequence",
+        table = <T as Component<DB>>::table(),
+        placeholder = <DB as DatabasePlaceholder>::placeholder(1),
+    )
+}
+
+/// Change made to a component, as recorded in the changelog.
+#[derive(Debug)]
+pub struct Change<EntityId, T> {
+    sequence: i64,
+    entity: EntityId,
+    operation: Operation,
+    recorded_at: i64,
+    component: T,
+}
+
+impl<EntityId, T> Change<EntityId, T> {
+    /// Position of the change in the changelog, increasing with every change.
+    pub fn sequence(&self) -> i64 {
+        self.sequence
+    }
+
+    /// Borrow the ID of the changed entity.
+    pub fn entity(&self) -> &EntityId {
+        &self.entity
+    }
+
+    /// Kind of change made to the component.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Time at which the change was recorded, in milliseconds since the unix epoch.
+    pub fn recorded_at(&self) -> i64 {
+        self.recorded_at
+    }
+
+    /// Borrow the component as it was after the change, or right before it was removed.
+    pub fn component(&self) -> &T {
+        &self.component
+    }
+
+    /// Consumes the change, returning the component.
+    pub fn into_component(self) -> T {
+        self.component
+    }
+}
+
+impl<'r, R, EntityId, T> FromRow<'r, R> for Change<EntityId, T>
+where
+    R: Row,
+    EntityId: for<'e> sqlx::Decode<'e, <R as sqlx::Row>::Database>
+        + sqlx::Type<<R as sqlx::Row>::Database>,
+    i64: for<'e> sqlx::Decode<'e, <R as sqlx::Row>::Database>
+        + sqlx::Type<<R as sqlx::Row>::Database>,
+    String: for<'e> sqlx::Decode<'e, <R as sqlx::Row>::Database>
+        + sqlx::Type<<R as sqlx::Row>::Database>,
+    T: Deserializeable<<R as Row>::Database>,
+    usize: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let mut row = OffsetRow::new(row);
+        let sequence = row.try_get::<i64>()?;
+        let entity = row.try_get::<EntityId>()?;
+        let operation = row.try_get::<String>()?;
+        let recorded_at = row.try_get::<i64>()?;
+
+        let operation = Operation::parse(&operation).ok_or_else(|| sqlx::Error::ColumnDecode {
+            index: "operation".to_string(),
+            source: format!("unknown operation {operation}").into(),
+        })?;
+
+        Ok(Change {
+            sequence,
+            entity,
+            operation,
+            recorded_at,
+            component: <T as Deserializeable<<R as Row>::Database>>::deserialize(&mut row)?,
+        })
+    }
+}
+
+/// Create the `erm_changelog` table, if it does not already exist.
+pub(crate) async fn create_table<DB, EntityId>(pool: &Pool<DB>) -> Result<(), sqlx::Error>
+where
+    DB: Changelog,
+    EntityId: sqlx::Type<DB>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+{
+    let entity = <EntityId as sqlx::Type<DB>>::type_info();
+    let sql = <DB as Changelog>::create_table(entity.name());
+
+    pool.execute(sql.as_str()).await?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Collections stored in child tables, using `#[erm(collection)]`.
+//!
+//! The elements of a `Vec<T>` field are stored as rows of a `<table>_<field>` table,
+//! along with their position within the collection. Rows are removed along with the
+//! component itself, and replaced entirely whenever it is updated.
+//!
+//! Collections are read by aggregating their rows into a JSON array, and therefore
+//! require the `json` feature, and elements implementing `DeserializeOwned`.
+//!
+//! Components with collections can be filtered by their elements:
+//!
+//! ```rust,ignore
+//! Article::FIELDS.tags.contains("rust".to_string())
+//! ```
+
+use std::{fmt::Write, marker::PhantomData};
+
+use sqlx::{query::QueryAs, Database};
+
+use crate::{archetype::DatabasePlaceholder, condition::Condition};
+
+/// Database-specific SQL used for reading collections.
+pub trait Collection: Database + DatabasePlaceholder {
+    /// Expression aggregating the values of the rows of `table` belonging to the entity of
+    /// the current row of `parent` into a JSON array, ordered by their position.
+    fn aggregate(table: &str, parent: &str) -> String;
+}
+
+#[cfg(feature = "sqlite")]
+impl Collection for sqlx::Sqlite {
+    fn aggregate(table: &str, parent: &str) -> String {
+        format!("(select json_group_array(value order by position) from {table} where {table}.entity = {parent}.entity)")
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl Collection for sqlx::Postgres {
+    fn aggregate(table: &str, parent: &str) -> String {
+        format!("(select coalesce(json_agg(value order by position), '[]'::json) from {table} where {table}.entity = {parent}.entity)")
+    }
+}
+
+/// `json_arrayagg` takes no `order by` of its own, but aggregates the rows of its window in
+/// order when used as a window function, so every row of the window holds the ordered array.
+#[cfg(feature = "mysql")]
+impl Collection for sqlx::MySql {
+    fn aggregate(table: &str, parent: &str) -> String {
+        format!("coalesce((select json_arrayagg(value) over (order by position rows between unbounded preceding and unbounded following) from {table} where {table}.entity = {parent}.entity limit 1), json_array())")
+    }
+}
+
+/// Collection field of a component, holding elements of type `T`.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectedCollection<T> {
+    table: &'static str,
+    _data: PhantomData<T>,
+}
+
+impl<T> ReflectedCollection<T> {
+    /// Collection stored in `table`.
+    pub const fn new(table: &'static str) -> Self {
+        Self {
+            table,
+            _data: PhantomData,
+        }
+    }
+
+    /// Matches entities whose collection contains `value`.
+    pub const fn contains(&self, value: T) -> Contains<T> {
+        Contains {
+            table: self.table,
+            parameter: value,
+        }
+    }
+}
+
+/// Matches entities whose collection contains the parameter, whatever the component
+/// the query's rows are named after.
+#[derive(Clone)]
+pub struct Contains<Parameter> {
+    table: &'static str,
+    parameter: Parameter,
+}
+
+impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB> for Contains<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        write!(
+            f,
+            "exists (select 1 from {table} where {table}.entity = {entity} and {table}.value = {placeholder})",
+            table = self.table,
+            entity = crate::cte::ENTITY,
+            placeholder = <DB as DatabasePlaceholder>::PLACEHOLDER
+        )
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query.bind(self.parameter)
+    }
+}
@@ -1,4 +1,9 @@
-use std::future::Future;
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    future::Future,
+    sync::{Mutex, OnceLock},
+};
 
 use sqlx::{Database, Pool};
 
@@ -10,6 +15,10 @@ use crate::{
 pub struct ColumnDefinition<DB: Database> {
     pub name: &'static str,
     pub type_info: <DB as Database>::TypeInfo,
+    /// Table of the component this column references, if any.
+    pub references: Option<&'static str>,
+    /// Whether rows should be removed when the referenced component is.
+    pub cascade: bool,
 }
 
 impl<DB: Database> ColumnDefinition<DB> {
@@ -20,29 +29,272 @@ impl<DB: Database> ColumnDefinition<DB> {
     pub fn type_info(&self) -> &<DB as Database>::TypeInfo {
         &self.type_info
     }
+
+    pub fn references(&self) -> Option<&'static str> {
+        self.references
+    }
+
+    pub fn cascade(&self) -> bool {
+        self.cascade
+    }
+}
+
+/// Marker for components deriving `#[erm(track_changes)]`, whose tables maintain
+/// `added_at` and `changed_at` ticks, bumped by [`Component::INSERT`] and [`Component::UPDATE`].
+///
+/// Ticks are taken from the counter in the `erm_ticks` table, which is shared by all
+/// tracked components, so no two writes get the same tick and ticks only ever increase.
+/// Writes hold the counter until their transaction completes, so ticks also increase in
+/// the order the writes are committed, and no change is missed by syncing from a tick.
+pub trait TrackChanges {}
+
+/// Database-specific SQL used for creating the counter of [`TrackChanges`] ticks.
+pub trait Ticks: Database {
+    /// Statements creating the `erm_ticks` table, holding the last tick in its single row.
+    const CREATE_TABLE: &'static str;
+}
+
+#[cfg(feature = "sqlite")]
+impl Ticks for sqlx::Sqlite {
+    const CREATE_TABLE: &'static str = "create table if not exists erm_ticks(id integer primary key check (id = 1), tick bigint not null);\ninsert or ignore into erm_ticks(id, tick) values (1, 0);";
+}
+
+#[cfg(feature = "postgres")]
+impl Ticks for sqlx::Postgres {
+    const CREATE_TABLE: &'static str = "create table if not exists erm_ticks(id integer primary key check (id = 1), tick bigint not null);\ninsert into erm_ticks(id, tick) values (1, 0) on conflict do nothing;";
+}
+
+#[cfg(feature = "mysql")]
+impl Ticks for sqlx::MySql {
+    const CREATE_TABLE: &'static str = "create table if not exists erm_ticks(id integer primary key check (id = 1), tick bigint not null);\ninsert ignore into erm_ticks(id, tick) values (1, 0);";
+}
+
+/// Components deriving `#[erm(soft_delete)]`, whose rows are kept when removed, and
+/// marked using a `deleted_at` column instead.
+///
+/// Soft-deleted components are excluded from queries, unless
+/// [`List::include_deleted`](crate::backend::List::include_deleted) is used, and can be
+/// brought back using [`Backend::restore`](crate::backend::Backend::restore).
+/// Inserting the component again replaces the soft-deleted row.
+pub trait SoftDelete<DB: Database>: Component<DB> {
+    /// Clears the `deleted_at` column of the entity's row.
+    const RESTORE: &'static str;
+
+    /// [`SoftDelete::RESTORE`], completed at runtime if it is a template, see [`Component::INSERT`].
+    fn restore_statement() -> &'static str {
+        Self::RESTORE
+    }
+
+    fn restore_component<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        query.query(Self::restore_statement(), |query| query)
+    }
+}
+
+/// Components deriving `#[erm(versioned)]`, whose tables have a `version` column
+/// incremented by every update.
+///
+/// [`Component::UPDATE`] only applies if the version bound as the last parameter is
+/// still current, see [`crate::version`].
+pub trait Versioned<DB: Database>: Component<DB> {
+    /// Updates the component regardless of its current version, as used by
+    /// [`Backend::update`](crate::backend::Backend::update).
+    const OVERWRITE: &'static str;
+
+    /// [`Versioned::OVERWRITE`], completed at runtime if it is a template, see [`Component::INSERT`].
+    fn overwrite_statement() -> &'static str {
+        Self::OVERWRITE
+    }
+
+    /// Queues the update of the component, if it is still at `version`, as the
+    /// [guarded](EntityPrefixedQuery::guarded_query) query.
+    fn update_version<'query, EntityId>(
+        &'query self,
+        version: i64,
+        query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query;
+}
+
+/// Lifecycle hooks for components deriving `#[erm(hooks)]`.
+///
+/// Hooks run on the connection of the transaction used by [`Archetype::insert`],
+/// [`Archetype::update`] and [`Archetype::remove`], so any statements they execute
+/// are committed along with the component. Returning an error rolls the transaction
+/// back, and the error is returned to the caller.
+///
+/// Hooks are not run for rows removed by [`Backend::despawn`](crate::backend::Backend::despawn).
+///
+/// [`Archetype::insert`]: crate::archetype::Archetype::insert
+/// [`Archetype::update`]: crate::archetype::Archetype::update
+/// [`Archetype::remove`]: crate::archetype::Archetype::remove
+pub trait Hooks<DB: Database>: Sized {
+    /// Called after the component has been inserted.
+    fn on_insert<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
+
+    /// Called after the component has been updated.
+    fn on_update<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
+
+    /// Called before the component is removed, while it can still be read.
+    fn on_remove<'hook, EntityId>(
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
 }
 
 /// Describes reading and writing from a Component-specific Table.
 pub trait Component<DB: Database>: Serializable<DB> + Deserializeable<DB> + Sized {
     const JOIN: &'static str = "inner";
+
+    /// Inserts the component, binding the entity followed by its fields.
+    ///
+    /// For components with a generic table name or flattened fields, the statements are
+    /// templates, containing `{table}` or the flattened columns, which are completed once
+    /// at runtime by [`Component::insert_statement`] and its siblings.
     const INSERT: &'static str;
+
+    /// Updates the component, binding the entity followed by its fields.
     const UPDATE: &'static str;
+
+    /// Removes the component, binding the entity.
     const DELETE: &'static str;
 
+    /// [`Component::INSERT`], completed at runtime if it is a template.
+    fn insert_statement() -> &'static str {
+        Self::INSERT
+    }
+
+    /// [`Component::UPDATE`], completed at runtime if it is a template.
+    fn update_statement() -> &'static str {
+        Self::UPDATE
+    }
+
+    /// [`Component::DELETE`], completed at runtime if it is a template.
+    fn delete_statement() -> &'static str {
+        Self::DELETE
+    }
+
+    /// Whether changes to the component are recorded in the [changelog](crate::changelog).
+    const CHANGELOG: bool = false;
+
     fn table() -> &'static str;
 
     fn columns() -> Vec<ColumnDefinition<DB>>;
 
+    /// Queue the removal of the component, as done by both [`Removable::remove`](crate::tables::Removable::remove)
+    /// and [`Backend::despawn`](crate::backend::Backend::despawn).
     fn remove_component<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
     where
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
     {
-        query.query(Self::DELETE, |query| query)
+        query.query(Self::delete_statement(), |query| query)
     }
 
-    fn create_component_table<'pool, EntityId>(
-        pool: &'pool Pool<DB>,
+    fn create_component_table<EntityId>(
+        pool: &Pool<DB>,
     ) -> impl Future<Output = Result<<DB as Database>::QueryResult, sqlx::Error>> + Send
     where
         EntityId: sqlx::Type<DB>;
 }
+
+/// Name of the type `T`, without its module path, for use in the table names
+/// of generic components, such as `i64` or `Vec_String`.
+pub fn type_name<T: ?Sized>() -> String {
+    let mut name = String::new();
+    let mut segment = String::new();
+
+    for character in std::any::type_name::<T>().chars() {
+        if character.is_alphanumeric() || character == '_' {
+            segment.push(character);
+        } else if character == ':' {
+            // Everything up to here was a module path.
+            segment.clear();
+        } else if !segment.is_empty() {
+            if !name.is_empty() {
+                name.push('_');
+            }
+
+            name.push_str(&segment);
+            segment.clear();
+        }
+    }
+
+    if !segment.is_empty() {
+        if !name.is_empty() {
+            name.push('_');
+        }
+
+        name.push_str(&segment);
+    }
+
+    name
+}
+
+/// Leaks the string returned by `init` once per type `K` and `key`.
+///
+/// Used for the table names and statements of generic components, which depend on
+/// the type parameters and therefore cannot be constants or `static`s.
+#[doc(hidden)]
+pub fn intern<K: ?Sized + 'static>(
+    key: &'static str,
+    init: impl FnOnce() -> String,
+) -> &'static str {
+    intern_pair::<K>(key, "", init)
+}
+
+/// Leaks the string returned by `init` once per type `K` and pair of keys, such as the
+/// prefix and name of a flattened column.
+pub(crate) fn intern_pair<K: ?Sized + 'static>(
+    first: &'static str,
+    second: &'static str,
+    init: impl FnOnce() -> String,
+) -> &'static str {
+    type Interned = Mutex<HashMap<(TypeId, &'static str, &'static str), &'static str>>;
+    static INTERNED: OnceLock<Interned> = OnceLock::new();
+
+    let interned = INTERNED.get_or_init(Default::default);
+    let key = (TypeId::of::<K>(), first, second);
+
+    if let Some(value) = interned.lock().unwrap().get(&key) {
+        return value;
+    }
+
+    // The lock is not held while initializing, since statements intern the table name.
+    let value = init();
+
+    interned
+        .lock()
+        .unwrap()
+        .entry(key)
+        .or_insert_with(|| Box::leak(value.into_boxed_str()))
+}
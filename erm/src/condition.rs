@@ -2,7 +2,7 @@ use std::fmt::Write;
 
 use sqlx::{query::QueryAs, Database};
 
-use crate::archetype::DatabasePlaceholder;
+use crate::{archetype::DatabasePlaceholder, reflect::ColumnName};
 
 pub trait Condition<'q, DB>: Sized
 where
@@ -23,6 +23,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct All;
 
 impl<'q, DB: Database> Condition<'q, DB> for All {
@@ -41,13 +42,18 @@ impl<'q, DB: Database> Condition<'q, DB> for All {
     }
 }
 
+#[derive(Clone)]
 pub struct Equality<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> Equality<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -79,13 +85,18 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct Inequality<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> Inequality<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -117,13 +128,18 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct GreaterThan<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> GreaterThan<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -155,13 +171,18 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct LessThan<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> LessThan<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -193,13 +214,18 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct GreaterThanOrEqual<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> GreaterThanOrEqual<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -232,13 +258,18 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct LessThanOrEqual<Parameter> {
-    column: &'static str,
+    column: ColumnName,
     parameter: Parameter,
 }
 
 impl<Parameter> LessThanOrEqual<Parameter> {
     pub const fn new(column: &'static str, value: Parameter) -> Self {
+        Self::reflected(ColumnName::Static(column), value)
+    }
+
+    pub(crate) const fn reflected(column: ColumnName, value: Parameter) -> Self {
         Self {
             column,
             parameter: value,
@@ -271,6 +302,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct And<A, B> {
     a: A,
     b: B,
@@ -304,6 +336,7 @@ impl<'q, DB: Database, A: Condition<'q, DB>, B: Condition<'q, DB>> Condition<'q,
         query
     }
 }
+#[derive(Clone)]
 pub struct Or<A, B> {
     a: A,
     b: B,
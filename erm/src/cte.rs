@@ -1,26 +1,127 @@
+use std::fmt::Write;
 use std::marker::PhantomData;
-use std::{collections::BTreeSet, fmt::Write};
 
 use std::fmt::Result;
 
 use sqlx::Database;
 
+use crate::archetype::DatabasePlaceholder;
+use crate::component::{Component, TrackChanges};
 use crate::prelude::Deserializeable;
 
 pub trait CommonTableExpression: std::fmt::Debug {
     fn table_name(&self, f: &mut dyn Write) -> Result;
     fn columns(&self, f: &mut dyn Write) -> Result;
+    /// Number of columns written by [`CommonTableExpression::columns`].
+    fn column_count(&self) -> usize;
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>];
     fn serialize(&self, f: &mut dyn Write) -> Result;
     fn optional(&self) -> bool {
         false
     }
+    fn recursive(&self) -> bool {
+        false
+    }
+
+    /// Adjust which rows of the component tables are read by this expression and its dependencies.
+    fn scope(&mut self, scope: Scope) {
+        let _ = scope;
+    }
+
+    /// Row lock to take on the component table read by this expression itself, see [`Scope::Lock`].
+    fn lock(&self) -> Option<Lock> {
+        None
+    }
+
+    /// Tables read by this expression itself, which are left untouched by default since
+    /// most expressions only read the expressions they depend on.
+    fn source_tables(&self, tables: &mut Vec<&'static str>) {
+        let _ = tables;
+    }
+}
+
+/// Adjusts which rows of component tables are read by a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Read components deriving `#[erm(history)]` as they were at the given timestamp,
+    /// in milliseconds since the unix epoch.
+    AsOf(i64),
+    /// Include rows of components deriving `#[erm(soft_delete)]` which have been removed.
+    IncludeDeleted,
+    /// Lock the rows of the returned entities' components until the end of the transaction.
+    ///
+    /// Only applies to components every returned entity has, so components which are
+    /// optional, excluded or belong to other entities, such as referenced ones, are not locked.
+    Lock(Lock),
+}
+
+impl Scope {
+    /// Whether the scope applies to components which the returned entities may not have.
+    fn applies_to_other_rows(self) -> bool {
+        !matches!(self, Scope::Lock(_))
+    }
+}
+
+/// Row lock taken by a query, see [`List::for_update`](crate::backend::List::for_update).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lock {
+    /// Wait for rows locked by other transactions to be released.
+    ForUpdate,
+    /// Skip rows locked by other transactions.
+    ForUpdateSkipLocked,
 }
 
+/// Databases supporting `for update` row locks.
+///
+/// SQLite has no row locks, since it serializes all writes to the database,
+/// so queries there take no locks and cannot use [`Lock`].
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not support row locks",
+    note = "SQLite serializes all writes to the database, so queries need no row locks there"
+)]
+pub trait RowLocking: Database {}
+
+#[cfg(feature = "postgres")]
+impl RowLocking for sqlx::Postgres {}
+
+#[cfg(feature = "mysql")]
+impl RowLocking for sqlx::MySql {}
+
 #[derive(Debug)]
 pub struct Extract {
     pub table: &'static str,
     pub columns: &'static [&'static str],
+    /// Columns computed by the expressions paired with them, which follow the other columns,
+    /// such as the aggregates of [collections](crate::collection).
+    pub computed: &'static [(&'static str, String)],
+    /// Child tables holding the items of the component's [collections](crate::collection).
+    pub collections: &'static [&'static str],
+    /// Whether past versions are kept in a `<table>_history` table, see [`crate::history`].
+    pub history: bool,
+    /// Whether removed rows are kept, and marked using their `deleted_at` column.
+    pub soft_delete: bool,
+    /// Point in time to read the `<table>_history` table at, instead of the component table.
+    pub as_of: Option<i64>,
+    /// Whether soft-deleted rows are read as well.
+    pub include_deleted: bool,
+    /// Row lock taken on the rows read.
+    pub lock: Option<Lock>,
+}
+
+impl Extract {
+    pub const fn new(table: &'static str, columns: &'static [&'static str]) -> Self {
+        Extract {
+            table,
+            columns,
+            computed: &[],
+            collections: &[],
+            history: false,
+            soft_delete: false,
+            as_of: None,
+            include_deleted: false,
+            lock: None,
+        }
+    }
 }
 
 impl CommonTableExpression for Extract {
@@ -33,9 +134,17 @@ impl CommonTableExpression for Extract {
             write!(f, ",\n      __cte_{}__{}", self.table, column)?
         }
 
+        for (column, _) in self.computed {
+            write!(f, ",\n      __cte_{}__{}", self.table, column)?
+        }
+
         Ok(())
     }
 
+    fn column_count(&self) -> usize {
+        self.columns.len() + self.computed.len()
+    }
+
     fn serialize(&self, f: &mut dyn Write) -> Result {
         write!(
             f,
@@ -50,13 +159,54 @@ impl CommonTableExpression for Extract {
                 column = column
             )?
         }
-        write!(f, "\n    from\n      ")?;
-        self.table_name(f)
+
+        for (column, expression) in self.computed {
+            write!(
+                f,
+                ",\n      {expression} as __cte_{table}__{column}",
+                table = self.table,
+            )?
+        }
+
+        // Versions are closed when a component is removed, soft-deleted or not,
+        // so the history table never holds deleted rows.
+        match self.as_of {
+            Some(timestamp) if self.history => write!(
+                f,
+                "\n    from\n      {table}_history\n    where\n      valid_from <= {timestamp} and (valid_to is null or valid_to > {timestamp})",
+                table = self.table
+            )?,
+            _ if self.soft_delete && !self.include_deleted => write!(
+                f,
+                "\n    from\n      {}\n    where\n      deleted_at is null",
+                self.table
+            )?,
+            _ => write!(f, "\n    from\n      {}", self.table)?,
+        }
+
+        Ok(())
     }
 
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &[]
     }
+
+    fn scope(&mut self, scope: Scope) {
+        match scope {
+            Scope::AsOf(timestamp) => self.as_of = Some(timestamp),
+            Scope::IncludeDeleted => self.include_deleted = true,
+            Scope::Lock(lock) => self.lock = Some(lock),
+        }
+    }
+
+    fn lock(&self) -> Option<Lock> {
+        self.lock
+    }
+
+    fn source_tables(&self, tables: &mut Vec<&'static str>) {
+        tables.push(self.table);
+        tables.extend(self.collections);
+    }
 }
 
 #[derive(Debug)]
@@ -69,10 +219,19 @@ impl CommonTableExpression for Optional {
         self.inner.table_name(f)
     }
 
+    /// Includes the entity column of the inner expression, which is only null if it was
+    /// not joined, since its other columns may be nullable or missing entirely.
     fn columns(&self, f: &mut dyn Write) -> Result {
+        write!(f, ",\n      __cte_")?;
+        self.inner.table_name(f)?;
+        write!(f, "__entity")?;
         self.inner.columns(f)
     }
 
+    fn column_count(&self) -> usize {
+        1 + self.inner.column_count()
+    }
+
     fn serialize(&self, f: &mut dyn Write) -> Result {
         self.inner.serialize(f)
     }
@@ -84,6 +243,17 @@ impl CommonTableExpression for Optional {
     fn optional(&self) -> bool {
         true
     }
+
+    fn recursive(&self) -> bool {
+        self.inner.recursive()
+    }
+
+    /// The nullable side of an outer join can't be locked.
+    fn scope(&mut self, scope: Scope) {
+        if scope.applies_to_other_rows() {
+            self.inner.scope(scope)
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -93,16 +263,18 @@ pub struct Merge {
 
 impl CommonTableExpression for Merge {
     fn table_name(&self, f: &mut dyn Write) -> Result {
-        let mut tables = self.tables.iter();
-        let first = tables.next().unwrap();
-        first.table_name(f)?;
-
-        for table in tables {
-            write!(f, "_")?;
-            table.table_name(f)?;
-        }
+        shortened(f, |f| {
+            let mut tables = self.tables.iter();
+            let first = tables.next().unwrap();
+            first.table_name(f)?;
+
+            for table in tables {
+                write!(f, "_")?;
+                table.table_name(f)?;
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn columns(&self, f: &mut dyn Write) -> Result {
@@ -113,17 +285,21 @@ impl CommonTableExpression for Merge {
         Ok(())
     }
 
+    fn column_count(&self) -> usize {
+        self.tables.iter().map(|table| table.column_count()).sum()
+    }
+
     fn serialize(&self, f: &mut dyn Write) -> Result {
         let mut tables = self.tables.iter();
         let first = tables.next().unwrap();
 
-        write!(f, "{}", "    select\n      __cte_")?;
+        write!(f, "    select\n      __cte_")?;
         first.table_name(f)?;
         write!(f, "__entity as __cte_")?;
         self.table_name(f)?;
         write!(f, "__entity")?;
         self.columns(f)?;
-        write!(f, "{}", "\n    from\n      __cte_")?;
+        write!(f, "\n    from\n      __cte_")?;
         first.table_name(f)?;
 
         for table in tables {
@@ -134,7 +310,7 @@ impl CommonTableExpression for Merge {
             }
             table.table_name(f)?;
             write!(f, "\n    on\n      __cte_")?;
-            self.table_name(f)?;
+            first.table_name(f)?;
             write!(f, "__entity = __cte_")?;
             table.table_name(f)?;
             write!(f, "__entity")?;
@@ -146,6 +322,104 @@ impl CommonTableExpression for Merge {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.tables
     }
+
+    fn scope(&mut self, scope: Scope) {
+        for table in &mut self.tables {
+            table.scope(scope);
+        }
+    }
+}
+
+/// Entities matching at least one of the variants, with the columns of each variant
+/// left joined onto them, preceded by its entity column which is null if it did not match.
+#[derive(Debug)]
+pub struct OneOf {
+    pub variants: Vec<Box<dyn CommonTableExpression>>,
+}
+
+impl CommonTableExpression for OneOf {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        shortened(f, |f| {
+            let mut variants = self.variants.iter();
+            let first = variants.next().unwrap();
+            first.table_name(f)?;
+
+            for variant in variants {
+                write!(f, "_or_")?;
+                variant.table_name(f)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        for variant in &self.variants {
+            write!(f, ",\n      __cte_")?;
+            variant.table_name(f)?;
+            write!(f, "__entity")?;
+            variant.columns(f)?;
+        }
+
+        Ok(())
+    }
+
+    fn column_count(&self) -> usize {
+        self.variants
+            .iter()
+            .map(|variant| 1 + variant.column_count())
+            .sum()
+    }
+
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        write!(f, "    select\n      __cte_")?;
+        self.table_name(f)?;
+        write!(f, "__entities.entity as __cte_")?;
+        self.table_name(f)?;
+        write!(f, "__entity")?;
+        self.columns(f)?;
+        write!(f, "\n    from\n      (")?;
+
+        for (index, variant) in self.variants.iter().enumerate() {
+            if index != 0 {
+                write!(f, "\n        union")?;
+            }
+
+            write!(f, "\n        select __cte_")?;
+            variant.table_name(f)?;
+            write!(f, "__entity as entity from __cte_")?;
+            variant.table_name(f)?;
+        }
+
+        write!(f, "\n      ) as __cte_")?;
+        self.table_name(f)?;
+        write!(f, "__entities")?;
+
+        for variant in &self.variants {
+            write!(f, "\n    left join\n      __cte_")?;
+            variant.table_name(f)?;
+            write!(f, "\n    on\n      __cte_")?;
+            self.table_name(f)?;
+            write!(f, "__entities.entity = __cte_")?;
+            variant.table_name(f)?;
+            write!(f, "__entity")?;
+        }
+
+        Ok(())
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.variants
+    }
+
+    /// Every entity has only one of the variants, which are therefore joined like optional components.
+    fn scope(&mut self, scope: Scope) {
+        if scope.applies_to_other_rows() {
+            for variant in &mut self.variants {
+                variant.scope(scope);
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -155,28 +429,34 @@ pub struct Include {
 
 impl CommonTableExpression for Include {
     fn table_name(&self, f: &mut dyn Write) -> Result {
-        self.inner[0].table_name(f)?;
-        write!(f, "_including_")?;
-        self.inner[1].table_name(f)
+        shortened(f, |f| {
+            self.inner[0].table_name(f)?;
+            write!(f, "_including_")?;
+            self.inner[1].table_name(f)
+        })
     }
 
     fn columns(&self, f: &mut dyn Write) -> Result {
         self.inner[0].columns(f)
     }
 
+    fn column_count(&self) -> usize {
+        self.inner[0].column_count()
+    }
+
     fn serialize(&self, f: &mut dyn Write) -> Result {
         write!(f, "    select\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "__entity as __cte_")?;
         self.table_name(f)?;
         write!(f, "__entity")?;
-        self.columns(f)?;
+        qualified_columns(f, self.inner[0].as_ref())?;
         write!(f, "\n    from\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "\n    inner join\n      __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "\n    on\n      __cte_")?;
-        self.table_name(f)?;
+        self.inner[0].table_name(f)?;
         write!(f, "__entity = __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "__entity")
@@ -185,6 +465,12 @@ impl CommonTableExpression for Include {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.inner
     }
+
+    fn scope(&mut self, scope: Scope) {
+        for inner in &mut self.inner {
+            inner.scope(scope);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -194,28 +480,34 @@ pub struct Exclude {
 
 impl CommonTableExpression for Exclude {
     fn table_name(&self, f: &mut dyn Write) -> Result {
-        self.inner[0].table_name(f)?;
-        write!(f, "_excluding_")?;
-        self.inner[1].table_name(f)
+        shortened(f, |f| {
+            self.inner[0].table_name(f)?;
+            write!(f, "_excluding_")?;
+            self.inner[1].table_name(f)
+        })
     }
 
     fn columns(&self, f: &mut dyn Write) -> Result {
         self.inner[0].columns(f)
     }
 
+    fn column_count(&self) -> usize {
+        self.inner[0].column_count()
+    }
+
     fn serialize(&self, f: &mut dyn Write) -> Result {
-        write!(f, "{}", "    select\n      __cte_")?;
+        write!(f, "    select\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "__entity as __cte_")?;
         self.table_name(f)?;
         write!(f, "__entity")?;
-        self.columns(f)?;
+        qualified_columns(f, self.inner[0].as_ref())?;
         write!(f, "\n    from\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "\n    left join\n      __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "\n    on\n      __cte_")?;
-        self.table_name(f)?;
+        self.inner[0].table_name(f)?;
         write!(f, "__entity = __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "__entity\n    where __cte_")?;
@@ -226,34 +518,440 @@ impl CommonTableExpression for Exclude {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.inner
     }
+
+    /// Excluded components are not returned, so they are not locked.
+    fn scope(&mut self, scope: Scope) {
+        self.inner[0].scope(scope);
+
+        if scope.applies_to_other_rows() {
+            self.inner[1].scope(scope);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Dereference {
+    pub source: String,
+    pub column: &'static str,
+}
+
+impl CommonTableExpression for Dereference {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        shortened(f, |f| {
+            write!(
+                f,
+                "{}_via_{}",
+                self.source,
+                self.column.trim_start_matches("__cte_")
+            )
+        })
+    }
+
+    fn columns(&self, _: &mut dyn Write) -> Result {
+        Ok(())
+    }
+
+    fn column_count(&self) -> usize {
+        0
+    }
+
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        write!(f, "    select distinct\n      {} as __cte_", self.column)?;
+        self.table_name(f)?;
+        write!(f, "__entity\n    from\n      __cte_{}", self.source)
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &[]
+    }
+}
+
+/// Joins the entities referenced by `column` of the first expression onto it.
+///
+/// When `fetch` is set, the columns of the referenced entities follow those of the first
+/// expression, renamed after their position since both sides may read the same components.
+#[derive(Debug)]
+pub struct Join {
+    pub inner: [Box<dyn CommonTableExpression>; 2],
+    pub column: &'static str,
+    pub fetch: bool,
+}
+
+impl CommonTableExpression for Join {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        shortened(f, |f| {
+            self.inner[0].table_name(f)?;
+            write!(f, "_joining_")?;
+            self.inner[1].table_name(f)
+        })
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].columns(f)?;
+
+        if self.fetch {
+            let offset = self.inner[0].column_count();
+
+            for index in 0..self.inner[1].column_count() {
+                write!(f, ",\n      __cte_ref{}", offset + index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn column_count(&self) -> usize {
+        let referenced = if self.fetch {
+            self.inner[1].column_count()
+        } else {
+            0
+        };
+
+        self.inner[0].column_count() + referenced
+    }
+
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        let mut source = String::new();
+        self.inner[0].table_name(&mut source)?;
+        let mut referenced = String::new();
+        self.inner[1].table_name(&mut referenced)?;
+
+        write!(
+            f,
+            "    select\n      __cte_{source}.__cte_{source}__entity as __cte_"
+        )?;
+        self.table_name(f)?;
+        write!(f, "__entity")?;
+
+        qualified_columns(f, self.inner[0].as_ref())?;
+
+        if self.fetch {
+            let offset = self.inner[0].column_count();
+
+            for (index, column) in column_names(self.inner[1].as_ref()).iter().enumerate() {
+                write!(
+                    f,
+                    ",\n      __cte_{referenced}.{column} as __cte_ref{}",
+                    offset + index
+                )?;
+            }
+        }
+
+        write!(
+            f,
+            "\n    from\n      __cte_{source}\n    inner join\n      __cte_{referenced}\n    on\n      __cte_{source}.{column} = __cte_{referenced}.__cte_{referenced}__entity",
+            column = self.column
+        )
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.inner
+    }
+
+    /// Referenced entities are not returned, so their components are not locked.
+    fn scope(&mut self, scope: Scope) {
+        self.inner[0].scope(scope);
+
+        if scope.applies_to_other_rows() {
+            self.inner[1].scope(scope);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Since {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub tick: i64,
+}
+
+impl CommonTableExpression for Since {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        shortened(f, |f| write!(f, "{}_{}_since", self.table, self.column))
+    }
+
+    fn columns(&self, _: &mut dyn Write) -> Result {
+        Ok(())
+    }
+
+    fn column_count(&self) -> usize {
+        0
+    }
+
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        write!(f, "    select\n      entity as __cte_")?;
+        self.table_name(f)?;
+        write!(
+            f,
+            "__entity\n    from\n      {table}\n    where\n      {column} > {tick}",
+            table = self.table,
+            column = self.column,
+            // Ticks are counted from zero, so earlier ones select the same rows.
+            tick = self.tick.max(0)
+        )
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &[]
+    }
+
+    fn source_tables(&self, tables: &mut Vec<&'static str>) {
+        tables.push(self.table);
+    }
+}
+
+/// Direction in which to walk a hierarchy of entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Walk from the root towards its children.
+    Descendants,
+    /// Walk from the root towards its parents.
+    Ancestors,
+}
+
+/// Databases able to walk hierarchies of entities, see [`Hierarchy`].
+pub trait Traversal: Database + DatabasePlaceholder {
+    /// Depth of the entities immediately related to the root, as a 64-bit integer.
+    const FIRST_DEPTH: &'static str;
+}
+
+#[cfg(feature = "sqlite")]
+impl Traversal for sqlx::Sqlite {
+    const FIRST_DEPTH: &'static str = "1";
+}
+
+#[cfg(feature = "postgres")]
+impl Traversal for sqlx::Postgres {
+    const FIRST_DEPTH: &'static str = "cast(1 as bigint)";
+}
+
+#[cfg(feature = "mysql")]
+impl Traversal for sqlx::MySql {
+    const FIRST_DEPTH: &'static str = "cast(1 as signed)";
+}
+
+/// Entities related to a root entity through the parent-child relationships stored in
+/// `column` of the expression `parents`, in the given direction.
+///
+/// Yields each related entity once, along with its depth relative to the root, which is
+/// `1` for immediate children or parents.
+#[derive(Debug)]
+pub struct Hierarchy {
+    name: String,
+    walk: [Box<dyn CommonTableExpression>; 1],
+}
+
+impl Hierarchy {
+    /// Hierarchy starting at the entity bound to the first parameter of the query.
+    pub fn new<DB: Traversal>(
+        parents: Box<dyn CommonTableExpression>,
+        column: &'static str,
+        direction: Direction,
+    ) -> Self {
+        let mut name = String::new();
+        shortened(&mut name, |f| {
+            parents.table_name(f)?;
+            match direction {
+                Direction::Descendants => write!(f, "_descendants"),
+                Direction::Ancestors => write!(f, "_ancestors"),
+            }
+        })
+        .unwrap();
+
+        let mut walk_name = String::new();
+        shortened(&mut walk_name, |f| write!(f, "{name}_walk")).unwrap();
+
+        Hierarchy {
+            name,
+            walk: [Box::new(Walk {
+                name: walk_name,
+                parents: [parents],
+                column,
+                direction,
+                placeholder: <DB as DatabasePlaceholder>::placeholder(1),
+                first_depth: <DB as Traversal>::FIRST_DEPTH,
+            })],
+        }
+    }
+}
+
+impl CommonTableExpression for Hierarchy {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        f.write_str(&self.name)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        write!(f, ",\n      __cte_{}__depth", self.name)
+    }
+
+    fn column_count(&self) -> usize {
+        1
+    }
+
+    /// Entities within cycles are walked several times, so only their shortest path is kept.
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        let mut walk = String::new();
+        self.walk[0].table_name(&mut walk)?;
+
+        write!(
+            f,
+            "    select\n      __cte_{walk}__entity as __cte_{name}__entity,\n      min(__cte_{walk}__depth) as __cte_{name}__depth\n    from\n      __cte_{walk}\n    group by\n      __cte_{walk}__entity",
+            name = self.name,
+        )
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.walk
+    }
+
+    fn scope(&mut self, scope: Scope) {
+        self.walk[0].scope(scope)
+    }
+}
+
+/// Recursive walk of a [`Hierarchy`], which yields entities once for each path to them.
+///
+/// Paths are at most as long as the number of parent-child relationships, which stops
+/// the walk from following cycles forever.
+#[derive(Debug)]
+struct Walk {
+    name: String,
+    parents: [Box<dyn CommonTableExpression>; 1],
+    column: &'static str,
+    direction: Direction,
+    placeholder: String,
+    first_depth: &'static str,
+}
+
+impl CommonTableExpression for Walk {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        f.write_str(&self.name)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        write!(f, ",\n      __cte_{}__depth", self.name)
+    }
+
+    fn column_count(&self) -> usize {
+        1
+    }
+
+    fn serialize(&self, f: &mut dyn Write) -> Result {
+        let mut parents = String::new();
+        self.parents[0].table_name(&mut parents)?;
+        let entity = format!("__cte_{parents}__entity");
+
+        // Descendants are found by looking for rows whose parent is already known,
+        // while ancestors are found by looking up the parent of rows already known.
+        let (origin, target) = match self.direction {
+            Direction::Descendants => (entity.as_str(), self.column),
+            Direction::Ancestors => (self.column, entity.as_str()),
+        };
+
+        write!(
+            f,
+            "    select\n      {origin} as __cte_{name}__entity,\n      {first_depth} as __cte_{name}__depth\n    from\n      __cte_{parents}\n    where\n      {target} = {placeholder}",
+            name = self.name,
+            first_depth = self.first_depth,
+            placeholder = self.placeholder,
+        )?;
+        write!(
+            f,
+            "\n    union all\n    select\n      __cte_{parents}.{origin},\n      __cte_{name}.__cte_{name}__depth + 1\n    from\n      __cte_{parents}\n    inner join\n      __cte_{name}\n    on\n      __cte_{parents}.{target} = __cte_{name}.__cte_{name}__entity\n    where\n      __cte_{name}.__cte_{name}__depth < (select count(*) from __cte_{parents})",
+            name = self.name,
+        )
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.parents
+    }
+
+    fn recursive(&self) -> bool {
+        true
+    }
+
+    fn scope(&mut self, scope: Scope) {
+        self.parents[0].scope(scope)
+    }
 }
 
 pub(crate) fn serialize(
     cte: &dyn CommonTableExpression,
 ) -> ::core::result::Result<String, std::fmt::Error> {
-    let mut ctes = BTreeSet::new();
+    let mut projection = String::from("__cte_");
+    cte.table_name(&mut projection)?;
+    projection.push_str("__entity");
+    cte.columns(&mut projection)?;
+
+    serialize_projection(cte, &projection)
+}
+
+/// Longest name of an expression combining others, which leaves room for the prefix
+/// and suffixes of its columns within the 63 bytes Postgres allows for identifiers.
+const MAX_NAME_LENGTH: usize = 47;
+
+/// Writes the name produced by `name`, shortened to [`MAX_NAME_LENGTH`].
+fn shortened(f: &mut dyn Write, name: impl FnOnce(&mut dyn Write) -> Result) -> Result {
+    crate::identifier::shortened(f, MAX_NAME_LENGTH, name)
+}
+
+/// Names of the columns of `cte`, which [`CommonTableExpression::columns`] lists
+/// as a comma-separated list.
+fn column_names(cte: &dyn CommonTableExpression) -> Vec<String> {
+    let mut columns = String::new();
+    cte.columns(&mut columns).unwrap();
+
+    columns
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Writes the columns of `cte` qualified by its name, for selecting them from a join
+/// whose other side may read the same components.
+fn qualified_columns(f: &mut dyn Write, cte: &dyn CommonTableExpression) -> Result {
+    let mut name = String::new();
+    cte.table_name(&mut name)?;
+
+    for column in column_names(cte) {
+        write!(f, ",\n      __cte_{name}.{column}")?;
+    }
+
+    Ok(())
+}
+
+/// Column of the final select holding the entity of each row, which conditions can
+/// correlate subqueries on, since the name of the final expression depends on the query.
+pub(crate) const ENTITY: &str = "__entity";
+
+/// Serializes the common table expression, selecting only `projection`
+/// from the final table instead of every column.
+pub(crate) fn serialize_projection(
+    cte: &dyn CommonTableExpression,
+    projection: &str,
+) -> ::core::result::Result<String, std::fmt::Error> {
+    // Expressions are kept in dependency order, since Postgres does not allow
+    // referencing an expression defined further down in a non-recursive `with`.
+    let mut ctes = Vec::new();
 
-    #[derive(Eq, Ord)]
     struct SerializedExpression {
         name: String,
         contents: String,
     }
 
-    impl PartialEq for SerializedExpression {
-        fn eq(&self, other: &Self) -> bool {
-            self.name == other.name
-        }
-    }
-
-    impl PartialOrd for SerializedExpression {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            self.name.partial_cmp(&other.name)
-        }
+    fn is_recursive(cte: &dyn CommonTableExpression) -> bool {
+        cte.recursive()
+            || cte
+                .dependencies()
+                .iter()
+                .any(|dependency| is_recursive(dependency.as_ref()))
     }
 
     fn serialize_into(
         cte: &dyn CommonTableExpression,
-        ctes: &mut BTreeSet<SerializedExpression>,
+        ctes: &mut Vec<SerializedExpression>,
     ) -> Result {
         let mut serialized = SerializedExpression {
             name: String::new(),
@@ -262,28 +960,41 @@ pub(crate) fn serialize(
 
         cte.table_name(&mut serialized.name)?;
 
-        if !ctes.contains(&serialized) {
+        let exists = |ctes: &[SerializedExpression]| {
+            ctes.iter().any(|existing| existing.name == serialized.name)
+        };
+
+        if !exists(ctes) {
             for dependency in cte.dependencies() {
                 serialize_into(dependency.as_ref(), ctes)?;
             }
 
-            write!(
+            // Archetypes of a single component share their name with that component.
+            if exists(ctes) {
+                return Ok(());
+            }
+
+            writeln!(
                 serialized.contents,
-                "  __cte_{table_name} as (\n",
+                "  __cte_{table_name} as (",
                 table_name = serialized.name
             )
             .unwrap();
 
             cte.serialize(&mut serialized.contents)?;
             serialized.contents.push_str("\n  )");
-            ctes.insert(serialized);
+            ctes.push(serialized);
         }
         Ok(())
     }
 
     serialize_into(cte, &mut ctes)?;
 
-    let mut statement = String::from("with\n");
+    let mut statement = if is_recursive(cte) {
+        String::from("with recursive\n")
+    } else {
+        String::from("with\n")
+    };
     for (index, serialized_cte) in ctes.into_iter().enumerate() {
         if index != 0 {
             statement.push_str(",\n");
@@ -291,103 +1002,359 @@ pub(crate) fn serialize(
         statement.push_str(&serialized_cte.contents);
     }
 
-    statement.push_str("\nselect * from __cte_");
-    cte.table_name(&mut statement)?;
-    statement.push_str("\n");
+    let mut name = String::new();
+    cte.table_name(&mut name)?;
+    writeln!(
+        statement,
+        "\nselect {projection} from (select __cte_{name}.*, __cte_{name}__entity as {ENTITY} from __cte_{name}) as __cte_{name}"
+    )?;
+
+    // Locks apply to the rows of the component tables themselves, which are joined
+    // so the final select can lock only the rows of the entities it returns.
+    let mut locked = Vec::new();
+    locks(cte, &mut locked)?;
+    for (table, _) in locked {
+        writeln!(
+            statement,
+            "  join {table} on {table}.entity = __cte_{name}.__cte_{name}__entity"
+        )?;
+    }
 
     Ok(statement)
 }
 
+/// Collects the component tables locked by the expression and its dependencies.
+fn locks(cte: &dyn CommonTableExpression, locked: &mut Vec<(String, Lock)>) -> Result {
+    if let Some(lock) = cte.lock() {
+        let mut table = String::new();
+        cte.table_name(&mut table)?;
+
+        if !locked.iter().any(|(existing, _)| *existing == table) {
+            locked.push((table, lock));
+        }
+    }
+
+    for dependency in cte.dependencies() {
+        locks(dependency.as_ref(), locked)?;
+    }
+
+    Ok(())
+}
+
+/// Locking clause ending a query over `cte`, which locks the component tables joined
+/// by [`serialize_projection`], or nothing if the query takes no locks.
+pub(crate) fn lock_clause(
+    cte: &dyn CommonTableExpression,
+) -> ::core::result::Result<String, std::fmt::Error> {
+    let mut locked = Vec::new();
+    locks(cte, &mut locked)?;
+
+    if locked.is_empty() {
+        return Ok(String::new());
+    }
+
+    let tables = locked
+        .iter()
+        .map(|(table, _)| table.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let skip_locked = locked
+        .iter()
+        .any(|(_, lock)| *lock == Lock::ForUpdateSkipLocked);
+
+    Ok(format!(
+        "\nfor update of {tables}{}",
+        if skip_locked { " skip locked" } else { "" }
+    ))
+}
+
 pub trait Filter<DB: Database> {
-    fn cte(cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression>;
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression>;
 }
 
 // This is for the empty unfiltered case.
 impl<DB: Database> Filter<DB> for () {
-    fn cte(cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        cte
+    }
+}
+
+/// Applies a [`Scope`] to every component read by the query, including the components
+/// read by the `inner` filters.
+pub struct Scoped<F> {
+    scope: Scope,
+    inner: F,
+}
+
+impl<F> Scoped<F> {
+    pub fn new(scope: Scope, inner: F) -> Self {
+        Scoped { scope, inner }
+    }
+}
+
+impl<DB: Database, F: Filter<DB>> Filter<DB> for Scoped<F> {
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        let mut cte = self.inner.cte(cte);
+        cte.scope(self.scope);
         cte
     }
 }
 
 pub struct With<T>(PhantomData<T>);
 
+impl<T> Default for With<T> {
+    fn default() -> Self {
+        With(PhantomData)
+    }
+}
+
 impl<T, DB: Database> Filter<DB> for With<T>
 where
     T: Deserializeable<DB>,
 {
-    fn cte(cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
         Box::new(Include {
             inner: [cte, <T as Deserializeable<DB>>::cte()],
         })
     }
 }
+
 pub struct Without<T>(PhantomData<T>);
 
+impl<T> Default for Without<T> {
+    fn default() -> Self {
+        Without(PhantomData)
+    }
+}
+
 impl<T, DB: Database> Filter<DB> for Without<T>
 where
     T: Deserializeable<DB>,
 {
-    fn cte(cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
         Box::new(Exclude {
             inner: [cte, <T as Deserializeable<DB>>::cte()],
         })
     }
 }
 
+/// Include only entities whose component `T` was inserted or updated after `tick`.
+pub struct Changed<T> {
+    tick: i64,
+    _data: PhantomData<T>,
+}
+
+impl<T: TrackChanges> Changed<T> {
+    pub const fn since(tick: i64) -> Self {
+        Changed {
+            tick,
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<T, DB: Database> Filter<DB> for Changed<T>
+where
+    T: Component<DB> + TrackChanges,
+{
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        Box::new(Include {
+            inner: [
+                cte,
+                Box::new(Since {
+                    table: <T as Component<DB>>::table(),
+                    column: "changed_at",
+                    tick: self.tick,
+                }),
+            ],
+        })
+    }
+}
+
+/// Include only entities whose component `T` was inserted after `tick`.
+pub struct Added<T> {
+    tick: i64,
+    _data: PhantomData<T>,
+}
+
+impl<T: TrackChanges> Added<T> {
+    pub const fn since(tick: i64) -> Self {
+        Added {
+            tick,
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<T, DB: Database> Filter<DB> for Added<T>
+where
+    T: Component<DB> + TrackChanges,
+{
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        Box::new(Include {
+            inner: [
+                cte,
+                Box::new(Since {
+                    table: <T as Component<DB>>::table(),
+                    column: "added_at",
+                    tick: self.tick,
+                }),
+            ],
+        })
+    }
+}
+
+/// Restricts the query to entities whose reference `column` points
+/// to an entity matching the filter `F`.
+///
+/// Constructed using [`List::join_ref`](crate::backend::List::join_ref).
+pub struct JoinRef<F> {
+    column: &'static str,
+    filter: F,
+}
+
+impl<F> JoinRef<F> {
+    pub const fn new(column: &'static str, filter: F) -> Self {
+        JoinRef { column, filter }
+    }
+}
+
+impl<F, DB: Database> Filter<DB> for JoinRef<F>
+where
+    F: Filter<DB>,
+{
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        let mut source = String::new();
+        cte.table_name(&mut source).unwrap();
+
+        let referenced = self.filter.cte(Box::new(Dereference {
+            source,
+            column: self.column,
+        }));
+
+        Box::new(Join {
+            inner: [cte, referenced],
+            column: self.column,
+            fetch: false,
+        })
+    }
+}
+
+/// Restricts the query to entities whose reference `column` points to an entity
+/// with the components `R` matching the filter `F`, and reads these components.
+///
+/// Constructed using [`List::fetch_ref`](crate::backend::List::fetch_ref).
+pub struct FetchRef<R, F> {
+    column: &'static str,
+    filter: F,
+    _referenced: PhantomData<R>,
+}
+
+impl<R, F> FetchRef<R, F> {
+    pub const fn new(column: &'static str, filter: F) -> Self {
+        FetchRef {
+            column,
+            filter,
+            _referenced: PhantomData,
+        }
+    }
+}
+
+impl<R, F, DB: Database> Filter<DB> for FetchRef<R, F>
+where
+    R: Deserializeable<DB>,
+    F: Filter<DB>,
+{
+    fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+        let mut source = String::new();
+        cte.table_name(&mut source).unwrap();
+
+        let referenced = self.filter.cte(Box::new(Merge {
+            tables: vec![
+                Box::new(Dereference {
+                    source,
+                    column: self.column,
+                }),
+                <R as Deserializeable<DB>>::cte(),
+            ],
+        }));
+
+        Box::new(Join {
+            inner: [cte, referenced],
+            column: self.column,
+            fetch: true,
+        })
+    }
+}
+
+/// Filters applied to the entity on the other end of a reference.
+///
+/// See [`List::join_ref`](crate::backend::List::join_ref).
+pub struct Referenced<F = ()> {
+    filter: F,
+}
+
+impl Referenced {
+    pub(crate) const fn new() -> Self {
+        Referenced { filter: () }
+    }
+}
+
+impl<F> Referenced<F> {
+    /// Include only referenced entities which contain the components `Include`.
+    pub fn with<Include>(self) -> Referenced<(With<Include>, F)> {
+        Referenced {
+            filter: (With::default(), self.filter),
+        }
+    }
+
+    /// Exclude referenced entities which contain the components `Exclude`.
+    pub fn without<Exclude>(self) -> Referenced<(Without<Exclude>, F)> {
+        Referenced {
+            filter: (Without::default(), self.filter),
+        }
+    }
+
+    pub(crate) fn into_filter(self) -> F {
+        self.filter
+    }
+}
+
 macro_rules! impl_filter_for_tuple{
-    ($($list:ident),*) => {
+    ($($list:ident:$index:tt),*) => {
         impl<DB, $($list),*> Filter<DB> for ($($list,)*)
         where
             DB: Database,
             $($list: Filter<DB>,)*
         {
-            fn cte(cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
-                $(let cte = <$list as Filter<DB>>::cte(cte);)*
+            #[allow(non_snake_case)]
+            fn cte(&self, cte: Box<dyn CommonTableExpression>) -> Box<dyn CommonTableExpression> {
+                let ($($list,)*) = self;
+                $(let cte = <$list as Filter<DB>>::cte($list, cte);)*
                 cte
             }
         }
     }
 }
 
-impl_filter_for_tuple!(T1);
-impl_filter_for_tuple!(T1, T2);
-impl_filter_for_tuple!(T1, T2, T3);
-impl_filter_for_tuple!(T1, T2, T3, T4);
-impl_filter_for_tuple!(T1, T2, T3, T4, T5);
-impl_filter_for_tuple!(T1, T2, T3, T4, T5, T6);
-impl_filter_for_tuple!(T1, T2, T3, T4, T5, T6, T7);
-impl_filter_for_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+for_tuples!(impl_filter_for_tuple);
 
 #[test]
 fn test_build() {
-    let positions = Extract {
-        table: "positions",
-        columns: &["x", "y"],
-    };
+    let positions = Extract::new("positions", &["x", "y"]);
 
-    let names = Extract {
-        table: "named",
-        columns: &["first", "last"],
-    };
+    let names = Extract::new("named", &["first", "last"]);
 
     let merge = Merge {
         tables: vec![Box::new(positions), Box::new(names)],
     };
 
     let exclude = Exclude {
-        inner: [
-            Box::new(merge),
-            Box::new(Extract {
-                table: "address",
-                columns: &[],
-            }),
-        ],
+        inner: [Box::new(merge), Box::new(Extract::new("address", &[]))],
     };
 
-    let parents = Extract {
-        table: "parents",
-        columns: &[],
-    };
+    let parents = Extract::new("parents", &[]);
 
     let include = Include {
         inner: [Box::new(exclude), Box::new(parents)],
@@ -395,3 +1362,99 @@ fn test_build() {
 
     println!("{}", serialize(&include).unwrap());
 }
+
+#[test]
+fn test_lock() {
+    let mut pending = Merge {
+        tables: vec![
+            Box::new(Extract::new("jobs", &["name"])),
+            Box::new(Extract::new("pending", &[])),
+        ],
+    };
+
+    pending.scope(Scope::Lock(Lock::ForUpdateSkipLocked));
+
+    let sql = serialize(&pending).unwrap();
+    assert!(!sql.contains("for update"));
+    assert!(sql.contains("join jobs on jobs.entity = "));
+    assert!(sql.contains("join pending on pending.entity = "));
+    assert_eq!(
+        lock_clause(&pending).unwrap(),
+        "\nfor update of jobs, pending skip locked"
+    );
+}
+
+#[test]
+fn test_lock_excluded() {
+    let mut unpaused = Exclude {
+        inner: [
+            Box::new(Extract::new("jobs", &["name"])),
+            Box::new(Extract::new("paused", &[])),
+        ],
+    };
+
+    unpaused.scope(Scope::Lock(Lock::ForUpdate));
+
+    assert_eq!(lock_clause(&unpaused).unwrap(), "\nfor update of jobs");
+}
+
+#[test]
+fn test_filter_tuples() {
+    fn filter<F: Filter<sqlx::Sqlite>>(filters: F) -> Box<dyn CommonTableExpression> {
+        filters.cte(Box::new(Extract::new("positions", &["x", "y"])))
+    }
+
+    let cte = filter((
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+    ));
+    assert!(serialize(cte.as_ref()).is_ok());
+}
+
+#[test]
+fn test_one_of() {
+    let shapes = OneOf {
+        variants: vec![
+            Box::new(Extract::new("circles", &["radius"])),
+            Box::new(Extract::new("rects", &["width", "height"])),
+        ],
+    };
+
+    let sql = serialize(&shapes).unwrap();
+    println!("{sql}");
+
+    assert_eq!(sql.matches("union").count(), 1);
+    assert_eq!(shapes.column_count(), 5);
+}
+
+#[test]
+fn test_since() {
+    let since = Since {
+        table: "a_component_with_a_rather_long_name",
+        column: "changed_at",
+        tick: -1,
+    };
+
+    let mut name = String::new();
+    since.table_name(&mut name).unwrap();
+    assert!(name.len() <= MAX_NAME_LENGTH);
+
+    let sql = serialize(&since).unwrap();
+    println!("{sql}");
+
+    assert!(sql.contains("changed_at > 0"));
+}
@@ -1,4 +1,6 @@
-use sqlx::{query::Query, Database};
+use std::ops::Deref;
+
+use sqlx::{encode::IsNull, error::BoxDynError, query::Query, Database, Decode, Encode, Type};
 
 pub struct EntityPrefixedQuery<'q, DB, EntityId>
 where
@@ -6,6 +8,9 @@ where
 {
     pub queries: Vec<Query<'q, DB, <DB as Database>::Arguments<'q>>>,
     pub entity: EntityId,
+    /// Index of the query which must affect a row, such as the update of a versioned
+    /// component, which otherwise conflicts, see [`EntityPrefixedQuery::guarded_query`].
+    pub guarded: Option<usize>,
 }
 
 impl<'query, DB, EntityId> EntityPrefixedQuery<'query, DB, EntityId>
@@ -17,6 +22,7 @@ where
         EntityPrefixedQuery {
             queries: Vec::new(),
             entity,
+            guarded: None,
         }
     }
 
@@ -31,6 +37,90 @@ where
 
         self.queries.push(f(query));
     }
+
+    /// Queue a query which must affect a row, or the queries are rolled back.
+    pub fn guarded_query(
+        &mut self,
+        sql: &'static str,
+        f: impl Fn(
+            Query<'query, DB, <DB as Database>::Arguments<'query>>,
+        ) -> Query<'query, DB, <DB as Database>::Arguments<'query>>,
+    ) {
+        self.guarded = Some(self.queries.len());
+        self.query(sql, f);
+    }
+
+    /// Queue a statement which does not take the entity as its first argument.
+    pub fn statement(&mut self, sql: &'static str) {
+        self.queries.push(sqlx::query(sql));
+    }
+}
+
+/// Reference to another entity, stored as that entity's ID.
+///
+/// Fields of this type can be marked with `#[erm(references = Component)]` to
+/// create a foreign key to the referenced component's table, and can be
+/// followed in queries using [`List::join_ref`](crate::backend::List::join_ref).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityRef<EntityId>(pub EntityId);
+
+impl<EntityId> EntityRef<EntityId> {
+    /// Borrow the ID of the referenced entity.
+    pub fn id(&self) -> &EntityId {
+        &self.0
+    }
+
+    /// Consumes the reference, returning the ID of the referenced entity.
+    pub fn into_id(self) -> EntityId {
+        self.0
+    }
+}
+
+impl<EntityId> From<EntityId> for EntityRef<EntityId> {
+    fn from(entity: EntityId) -> Self {
+        EntityRef(entity)
+    }
+}
+
+impl<EntityId> Deref for EntityRef<EntityId> {
+    type Target = EntityId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<DB: Database, EntityId: Type<DB>> Type<DB> for EntityRef<EntityId> {
+    fn type_info() -> <DB as Database>::TypeInfo {
+        <EntityId as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &<DB as Database>::TypeInfo) -> bool {
+        <EntityId as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database, EntityId: Encode<'q, DB>> Encode<'q, DB> for EntityRef<EntityId> {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        self.0.encode_by_ref(buf)
+    }
+
+    fn produces(&self) -> Option<<DB as Database>::TypeInfo> {
+        self.0.produces()
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.size_hint()
+    }
+}
+
+impl<'r, DB: Database, EntityId: Decode<'r, DB>> Decode<'r, DB> for EntityRef<EntityId> {
+    fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        <EntityId as Decode<'r, DB>>::decode(value).map(EntityRef)
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +134,7 @@ mod tests {
         let mut insert = EntityPrefixedQuery::<'_, Sqlite, _> {
             queries: vec![],
             entity: 12345,
+            guarded: None,
         };
 
         insert.query("a", |query| query.bind(1));
@@ -0,0 +1,134 @@
+//! Plain structs stored as columns of the components embedding them, using `#[erm(flatten)]`.
+//!
+//! ```rust,ignore
+//! #[derive(Flatten)]
+//! struct Vec3 {
+//!     x: f64,
+//!     y: f64,
+//!     z: f64,
+//! }
+//!
+//! // Stored in the columns `pos_x, pos_y, pos_z, scale_x, scale_y, scale_z`.
+//! #[derive(Component)]
+//! struct Transform {
+//!     #[erm(flatten)]
+//!     pos: Vec3,
+//!     #[erm(flatten)]
+//!     scale: Vec3,
+//! }
+//! ```
+//!
+//! The columns of flattened fields follow the other columns of the component.
+
+use sqlx::{query::Query, Database};
+
+use crate::{component::ColumnDefinition, row::OffsetRow};
+
+/// Struct whose fields are stored as columns of the components embedding it.
+pub trait Flatten<DB: Database>: Sized {
+    /// Columns of the struct, named without the prefix of the embedding field.
+    fn columns() -> Vec<ColumnDefinition<DB>>;
+
+    /// SQL type definitions of the columns, such as `bigint not null`, in the same order.
+    fn definitions() -> Vec<String>;
+
+    fn serialize<'q>(
+        &'q self,
+        query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>;
+
+    fn deserialize(row: &mut OffsetRow<<DB as Database>::Row>) -> Result<Self, sqlx::Error>;
+}
+
+/// Reflection of a struct deriving `Flatten`, placed within the reflection of the
+/// components embedding it.
+///
+/// The reflection type is constructed using its `const fn prefixed(prefix: &'static str)`,
+/// given the prefix of its columns within the component's common table expression.
+pub trait ReflectFlattened {
+    type ReflectionType;
+}
+
+/// Name of the column `name`, prefixed by `prefix`, leaked once per combination.
+pub(crate) fn prefixed(prefix: &'static str, name: &'static str) -> &'static str {
+    crate::component::intern_pair::<dyn ReflectFlattened<ReflectionType = ()>>(prefix, name, || {
+        format!("{prefix}{name}")
+    })
+}
+
+/// Columns of the flattened fields of a component, in the order they are declared.
+///
+/// Used by the statements of components with flattened fields, which are formatted
+/// once, at runtime, since the columns are only known to the flattened types.
+#[doc(hidden)]
+pub struct FlattenedColumns<DB: Database> {
+    columns: Vec<ColumnDefinition<DB>>,
+    definitions: Vec<String>,
+}
+
+impl<DB: Database> Default for FlattenedColumns<DB> {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+            definitions: Vec::new(),
+        }
+    }
+}
+
+impl<DB: Database> FlattenedColumns<DB> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the columns of a field of type `T`, whose names are prefixed by `prefix`.
+    pub fn field<T: Flatten<DB>>(mut self, prefix: &'static str) -> Self {
+        self.columns
+            .extend(T::columns().into_iter().map(|column| ColumnDefinition {
+                name: prefixed(prefix, column.name),
+                ..column
+            }));
+        self.definitions.extend(T::definitions());
+        self
+    }
+
+    pub fn count(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.columns.iter().map(ColumnDefinition::name)
+    }
+
+    pub fn into_columns(self) -> Vec<ColumnDefinition<DB>> {
+        self.columns
+    }
+
+    /// Column names, each preceded by a comma, such as `, pos_x, pos_y`.
+    pub fn list(&self) -> String {
+        self.names().map(|name| format!(", {name}")).collect()
+    }
+
+    /// Placeholders of the columns, starting at `first`, each preceded by a comma.
+    pub fn placeholders(&self, character: char, first: usize) -> String {
+        (first..first + self.count())
+            .map(|index| format!(", {character}{index}"))
+            .collect()
+    }
+
+    /// Assignments of the columns to placeholders starting at `first`, such as `pos_x = ?3, pos_y = ?4`.
+    pub fn assignments(&self, character: char, first: usize) -> String {
+        self.names()
+            .zip(first..)
+            .map(|(name, index)| format!("{name} = {character}{index}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Column definitions for `create table` statements, each preceded by a comma.
+    pub fn definitions(&self) -> String {
+        self.names()
+            .zip(&self.definitions)
+            .map(|(name, definition)| format!(",\n  {name} {definition}"))
+            .collect()
+    }
+}
@@ -0,0 +1,59 @@
+//! Version history for components deriving `#[erm(history)]`.
+//!
+//! Every version of such a component is kept in a `<table>_history` table, along with the
+//! range of time in which it was valid, as milliseconds since the unix epoch. The current
+//! version has no end of validity.
+//!
+//! Past versions can be read using [`List::as_of`](crate::backend::List::as_of).
+
+use sqlx::Database;
+
+use crate::{archetype::DatabasePlaceholder, component::Component};
+
+/// Database-specific SQL used for maintaining component history.
+pub trait History: Database + DatabasePlaceholder {
+    /// Expression evaluating to the current time in milliseconds since the unix epoch.
+    const NOW: &'static str;
+}
+
+#[cfg(feature = "sqlite")]
+impl History for sqlx::Sqlite {
+    const NOW: &'static str = "cast((julianday('now') - 2440587.5) * 86400000 as integer)";
+}
+
+#[cfg(feature = "postgres")]
+impl History for sqlx::Postgres {
+    const NOW: &'static str = "cast(extract(epoch from clock_timestamp()) * 1000 as bigint)";
+}
+
+#[cfg(feature = "mysql")]
+impl History for sqlx::MySql {
+    const NOW: &'static str = "cast(unix_timestamp(now(3)) * 1000 as signed)";
+}
+
+/// Statement copying the current version of the component `T` into its history table,
+/// for the entity bound as the first parameter.
+pub fn open<DB: History, T: Component<DB>>() -> String {
+    let table = <T as Component<DB>>::table();
+    let columns = <T as Component<DB>>::columns()
+        .iter()
+        .map(|column| format!(", {}", column.name()))
+        .collect::<String>();
+
+    format!(
+        "insert into {table}_history(entity{columns}, valid_from, valid_to) select entity{columns}, {now}, null from {table} where entity = {placeholder}",
+        now = <DB as History>::NOW,
+        placeholder = <DB as DatabasePlaceholder>::placeholder(1),
+    )
+}
+
+/// Statement ending the validity of the current version of the component `T`,
+/// for the entity bound as the first parameter.
+pub fn close<DB: History, T: Component<DB>>() -> String {
+    format!(
+        "update {table}_history set valid_to = {now} where entity = {placeholder} and valid_to is null",
+        table = <T as Component<DB>>::table(),
+        now = <DB as History>::NOW,
+        placeholder = <DB as DatabasePlaceholder>::placeholder(1),
+    )
+}
@@ -0,0 +1,30 @@
+//! Names of the tables, indexes and expressions created by erm.
+
+use std::fmt::{Result, Write};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Writes the name produced by `name`, replacing its end with a hash if it is longer than
+/// `max_length`, since Postgres would otherwise truncate it and possibly merge distinct names.
+pub(crate) fn shortened(
+    f: &mut dyn Write,
+    max_length: usize,
+    name: impl FnOnce(&mut dyn Write) -> Result,
+) -> Result {
+    let mut full = String::new();
+    name(&mut full)?;
+
+    if full.len() <= max_length {
+        return f.write_str(&full);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    full.hash(&mut hasher);
+    let hash = format!("{:016x}", hasher.finish());
+
+    let mut end = max_length - hash.len() - 1;
+    while !full.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    write!(f, "{}_{hash}", &full[..end])
+}
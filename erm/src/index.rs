@@ -0,0 +1,96 @@
+//! Indexes on the columns of components, using `#[erm(index)]` and `#[erm(unique)]`.
+//!
+//! Indexes are created by [`Backend::register`](crate::backend::Backend::register), along
+//! with the component's table, and are named after the table and their columns. Names which
+//! are too long for Postgres and MySQL end in a hash instead.
+//!
+//! ```rust,ignore
+//! #[derive(Component)]
+//! #[erm(unique(fields = [first_name, last_name]))]
+//! struct Person {
+//!     first_name: String,
+//!     last_name: String,
+//!     #[erm(index)]
+//!     age: i64,
+//!     #[erm(unique)]
+//!     email: String,
+//! }
+//! ```
+//!
+//! Soft-deleted rows keep their values, and therefore still count towards unique indexes.
+
+use sqlx::Database;
+
+/// Database-specific SQL used for creating indexes.
+pub trait CreateIndex: Database {
+    /// Statement creating an index on `columns` of `table`, unless it exists already.
+    fn create_index(table: &str, columns: &[&str], unique: bool) -> String;
+}
+
+/// Longest index name, within the 63 bytes Postgres allows for identifiers and the 64 of MySQL.
+const MAX_NAME_LENGTH: usize = 63;
+
+/// Name of the index on `columns` of `table`, such as `Person_first_name_last_name_unique`.
+fn name(table: &str, columns: &[&str], unique: bool) -> String {
+    let kind = if unique { "unique" } else { "index" };
+
+    let mut name = String::new();
+    crate::identifier::shortened(&mut name, MAX_NAME_LENGTH, |f| {
+        write!(f, "{table}_{}_{kind}", columns.join("_"))
+    })
+    .unwrap();
+
+    name
+}
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn create_if_not_exists(table: &str, columns: &[&str], unique: bool) -> String {
+    format!(
+        "create {unique}index if not exists {name} on {table}({columns});",
+        unique = if unique { "unique " } else { "" },
+        name = name(table, columns, unique),
+        columns = columns.join(", "),
+    )
+}
+
+#[cfg(feature = "sqlite")]
+impl CreateIndex for sqlx::Sqlite {
+    fn create_index(table: &str, columns: &[&str], unique: bool) -> String {
+        create_if_not_exists(table, columns, unique)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl CreateIndex for sqlx::Postgres {
+    fn create_index(table: &str, columns: &[&str], unique: bool) -> String {
+        create_if_not_exists(table, columns, unique)
+    }
+}
+
+/// MySQL has no `create index if not exists`, so the statement is only prepared
+/// if `information_schema` does not list the index yet.
+#[cfg(feature = "mysql")]
+impl CreateIndex for sqlx::MySql {
+    fn create_index(table: &str, columns: &[&str], unique: bool) -> String {
+        let name = name(table, columns, unique);
+
+        format!(
+            "set @erm_index = (select if(count(*) = 0, 'create {unique}index {name} on {table}({columns})', 'do 0') from information_schema.statistics where table_schema = database() and table_name = '{table}' and index_name = '{name}');\nprepare erm_index from @erm_index;\nexecute erm_index;\ndeallocate prepare erm_index;",
+            unique = if unique { "unique " } else { "" },
+            columns = columns.join(", "),
+        )
+    }
+}
+
+#[test]
+fn test_name() {
+    assert_eq!(name("Person", &["age"], false), "Person_age_index");
+
+    let columns = ["first_name", "middle_name", "last_name", "date_of_birth"];
+    let unique = name("RegisteredPerson", &columns, true);
+    let index = name("RegisteredPerson", &columns, false);
+
+    assert!(unique.len() <= MAX_NAME_LENGTH);
+    assert!(unique.starts_with("RegisteredPerson_first_name_middle_name"));
+    assert_ne!(unique, index);
+}
@@ -0,0 +1,140 @@
+//! Fields stored as JSON, using `#[erm(json)]`.
+//!
+//! Such fields hold any type implementing `Serialize` and `DeserializeOwned`, and are stored
+//! in a `jsonb` column on Postgres, a `json` column on MySQL and a `text` column on Sqlite.
+//!
+//! Values within the document can be filtered by their [JSON path](ReflectedColumn::json_path):
+//!
+//! ```rust,ignore
+//! Settings::FIELDS.prefs.json_path("$.theme").eq("dark".to_string())
+//! ```
+
+use std::fmt::Write;
+
+use sqlx::{query::QueryAs, Database};
+
+pub use sqlx::types::Json;
+
+use crate::{archetype::DatabasePlaceholder, condition::Condition, reflect::ReflectedColumn};
+
+/// Database-specific SQL used for reading values from JSON documents.
+pub trait JsonPath: Database + DatabasePlaceholder {
+    /// Expression extracting the scalar value at `path` from the JSON document in `column`,
+    /// to be compared against a parameter of the SQL type `type_info`.
+    ///
+    /// The path is a string literal, quoted and escaped already.
+    fn extract(column: &str, path: &str, type_info: &Self::TypeInfo) -> String;
+}
+
+#[cfg(feature = "sqlite")]
+impl JsonPath for sqlx::Sqlite {
+    fn extract(column: &str, path: &str, _: &Self::TypeInfo) -> String {
+        format!("json_extract({column}, {path})")
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl JsonPath for sqlx::Postgres {
+    /// Scalars are extracted as text, which is cast to the type of the parameter, since
+    /// Postgres does not compare text to other types.
+    fn extract(column: &str, path: &str, type_info: &Self::TypeInfo) -> String {
+        use sqlx::TypeInfo as _;
+
+        format!(
+            "cast(jsonb_path_query_first({column}, {path}) #>> '{{}}' as {})",
+            type_info.name()
+        )
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl JsonPath for sqlx::MySql {
+    fn extract(column: &str, path: &str, _: &Self::TypeInfo) -> String {
+        format!("json_unquote(json_extract({column}, {path}))")
+    }
+}
+
+impl<T> ReflectedColumn<Json<T>> {
+    /// Value at `path` within the JSON document, such as `$.theme` or `$.tags[0]`.
+    pub fn json_path(&self, path: &'static str) -> ReflectedPath {
+        ReflectedPath {
+            column: self.column_name(),
+            path,
+        }
+    }
+}
+
+/// Value within a JSON column, which conditions can be placed upon.
+///
+/// Documents are not typed, so each condition decides the type of value it compares against.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectedPath {
+    column: &'static str,
+    path: &'static str,
+}
+
+impl ReflectedPath {
+    fn compare<T>(&self, operator: &'static str, value: T) -> PathComparison<T> {
+        PathComparison {
+            column: self.column,
+            path: self.path,
+            operator,
+            parameter: value,
+        }
+    }
+
+    pub fn eq<T>(&self, value: T) -> PathComparison<T> {
+        self.compare("=", value)
+    }
+    pub fn ne<T>(&self, value: T) -> PathComparison<T> {
+        self.compare("<>", value)
+    }
+    pub fn gt<T>(&self, value: T) -> PathComparison<T> {
+        self.compare(">", value)
+    }
+    pub fn lt<T>(&self, value: T) -> PathComparison<T> {
+        self.compare("<", value)
+    }
+    pub fn ge<T>(&self, value: T) -> PathComparison<T> {
+        self.compare(">=", value)
+    }
+    pub fn le<T>(&self, value: T) -> PathComparison<T> {
+        self.compare("<=", value)
+    }
+}
+
+/// Compares the value at a path within a JSON column to a parameter.
+#[derive(Clone)]
+pub struct PathComparison<Parameter> {
+    column: &'static str,
+    path: &'static str,
+    operator: &'static str,
+    parameter: Parameter,
+}
+
+impl<'q, DB: JsonPath, Parameter> Condition<'q, DB> for PathComparison<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+        let path = format!("'{}'", self.path.replace('\'', "''"));
+
+        write!(
+            f,
+            "{} {} {}",
+            <DB as JsonPath>::extract(self.column, &path, &Parameter::type_info()),
+            self.operator,
+            <DB as DatabasePlaceholder>::PLACEHOLDER
+        )
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query.bind(self.parameter)
+    }
+}
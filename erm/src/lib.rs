@@ -34,7 +34,7 @@
 //!         x: 100,
 //!         y: 200
 //!     }
-//! )).await;
+//! )).await.unwrap();
 //!
 //! // Spawn a second named position
 //! let pos2 = backend.spawn(&(
@@ -45,7 +45,7 @@
 //!         x: -10000,
 //!         y: -5
 //!     }
-//! )).await;
+//! )).await.unwrap();
 //!
 //! # use futures::stream::StreamExt as _;
 //! // Construct an iterator over all components with a DisplayName & Position
@@ -62,30 +62,73 @@
 //! // name: Position 2 at -10000,-5
 //!
 //! // Remove the DisplayName component from our pos2 entity.
-//! backend.remove::<DisplayName>(&pos2).await;
+//! backend.remove::<DisplayName>(&pos2).await.unwrap();
 //!
 //! // Fetch the name of our first position.
 //! let pos1_name = backend.get::<DisplayName>(&pos1).await.unwrap();
 //! assert_eq!(pos1_name.name, "Position 1");
 //!
 //! // Update the name of our second position.
-//! backend.update(&pos2, &DisplayName { name: "Second Position".to_string() }).await;
+//! backend.update(&pos2, &DisplayName { name: "Second Position".to_string() }).await.unwrap();
 //!
 //! # }
 //! ```
 //!
 //! See [github.com/MathiasPius/erm](https://github.com/MathiasPius/erm/tree/main/examples) for more examples.
+//!
+//! ## Features
+//!
+//! The `sqlite`, `postgres` and `mysql` features enable the backend of each database.
+//!
+//! The `json` feature enables fields stored as JSON documents using `#[erm(json)]`, and is
+//! also required by `#[erm(collection)]` fields, since their rows are aggregated into a JSON
+//! array when read, so their elements must implement `DeserializeOwned`.
+
+/// Invokes `$m!` with the element types and indices of every tuple from 1 to 16 elements,
+/// such as `$m!(A:0, B:1)`, so the tuple implementations of all traits stay in sync.
+macro_rules! for_tuples {
+    ($m:ident) => {
+        $m!(A:0);
+        $m!(A:0, B:1);
+        $m!(A:0, B:1, C:2);
+        $m!(A:0, B:1, C:2, D:3);
+        $m!(A:0, B:1, C:2, D:3, E:4);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14);
+        $m!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15);
+    };
+}
 
+pub mod aggregate;
 pub mod archetype;
 pub mod backend;
+pub mod changelog;
+#[cfg(feature = "json")]
+pub mod collection;
 pub mod component;
 pub mod condition;
 pub mod cte;
 pub mod entity;
+pub mod flatten;
+pub mod history;
+mod identifier;
+pub mod index;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod reflect;
 pub mod row;
 pub mod serialization;
+pub mod subscription;
 pub mod tables;
+pub mod version;
 
 #[cfg(feature = "bundled")]
 pub use ::sqlx;
@@ -94,11 +137,14 @@ pub mod prelude {
     #[cfg(feature = "derive")]
     pub use erm_derive::*;
 
+    pub use crate::aggregate;
     pub use crate::archetype::Archetype;
     pub use crate::backend::*;
-    pub use crate::component::{ColumnDefinition, Component};
+    pub use crate::component::{ColumnDefinition, Component, Hooks};
     pub use crate::condition;
     pub use crate::cte::*;
+    pub use crate::entity::EntityRef;
+    pub use crate::flatten::Flatten;
     pub use crate::reflect::Reflect;
     pub use crate::serialization::{Deserializeable, Serializable};
     pub use crate::tables::Removable;
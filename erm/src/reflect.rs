@@ -11,37 +11,86 @@ pub trait Reflect {
 
 #[derive(Debug, Clone, Copy)]
 pub struct ReflectedColumn<T> {
-    column_name: &'static str,
+    column_name: ColumnName,
     _data: PhantomData<T>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ColumnName {
+    Static(&'static str),
+    /// Name of a column of a generic component, which depends on its type parameters.
+    Generic(fn() -> &'static str),
+    /// Name of a column of a struct flattened into a component, and its prefix.
+    Prefixed(&'static str, &'static str),
+}
+
 impl<T> ReflectedColumn<T> {
     pub const fn new(column_name: &'static str) -> Self {
         Self {
-            column_name,
+            column_name: ColumnName::Static(column_name),
+            _data: PhantomData,
+        }
+    }
+
+    /// Column of a generic component, whose name is only known at runtime.
+    pub const fn generic(column_name: fn() -> &'static str) -> Self {
+        Self {
+            column_name: ColumnName::Generic(column_name),
+            _data: PhantomData,
+        }
+    }
+
+    /// Column of a struct flattened into a component, see [`crate::flatten`].
+    pub const fn prefixed(prefix: &'static str, column_name: &'static str) -> Self {
+        Self {
+            column_name: ColumnName::Prefixed(prefix, column_name),
             _data: PhantomData,
         }
     }
+
+    /// Name of the column within the query's common table expression.
+    pub fn column_name(&self) -> &'static str {
+        self.column_name.resolve()
+    }
+}
+
+impl ColumnName {
+    /// Name of the column, which is interned on first use unless it is static.
+    pub(crate) fn resolve(self) -> &'static str {
+        match self {
+            ColumnName::Static(column_name) => column_name,
+            ColumnName::Generic(column_name) => column_name(),
+            ColumnName::Prefixed(prefix, column_name) => {
+                crate::flatten::prefixed(prefix, column_name)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.resolve())
+    }
 }
 
 impl<T> ReflectedColumn<T> {
     pub const fn eq(&self, value: T) -> Equality<T> {
-        Equality::new(self.column_name, value)
+        Equality::reflected(self.column_name, value)
     }
     pub const fn ne(&self, value: T) -> Inequality<T> {
-        Inequality::new(self.column_name, value)
+        Inequality::reflected(self.column_name, value)
     }
     pub const fn gt(&self, value: T) -> GreaterThan<T> {
-        GreaterThan::new(self.column_name, value)
+        GreaterThan::reflected(self.column_name, value)
     }
     pub const fn lt(&self, value: T) -> LessThan<T> {
-        LessThan::new(self.column_name, value)
+        LessThan::reflected(self.column_name, value)
     }
     pub const fn ge(&self, value: T) -> GreaterThanOrEqual<T> {
-        GreaterThanOrEqual::new(self.column_name, value)
+        GreaterThanOrEqual::reflected(self.column_name, value)
     }
     pub const fn le(&self, value: T) -> LessThanOrEqual<T> {
-        LessThanOrEqual::new(self.column_name, value)
+        LessThanOrEqual::reflected(self.column_name, value)
     }
 
     pub const fn equals(&self, value: T) -> Equality<T> {
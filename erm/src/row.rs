@@ -1,7 +1,8 @@
 use std::ops::{Deref, DerefMut};
 
-use sqlx::{prelude::FromRow, ColumnIndex, Decode, Row, ValueRef};
+use sqlx::{prelude::FromRow, ColumnIndex, Database, Decode, Row, ValueRef};
 
+use crate::cte::CommonTableExpression;
 use crate::serialization::Deserializeable;
 
 /// Wrapper around a database-specific row which tracks the offset
@@ -113,3 +114,105 @@ where
         })
     }
 }
+
+/// Components `T` of an entity, along with the components `R` of the entity it references.
+///
+/// Returned by queries using [`List::fetch_ref`](crate::backend::List::fetch_ref).
+#[derive(Debug)]
+pub struct Dereferenced<T, R> {
+    components: T,
+    referenced: R,
+}
+
+impl<T, R> Dereferenced<T, R> {
+    /// Borrow the components of the entity itself.
+    pub fn components(&self) -> &T {
+        &self.components
+    }
+
+    /// Borrow the components of the referenced entity.
+    pub fn referenced(&self) -> &R {
+        &self.referenced
+    }
+
+    /// Consumes the result, returning the components of the entity and those it references.
+    pub fn into_inner(self) -> (T, R) {
+        (self.components, self.referenced)
+    }
+}
+
+/// The expression is the one of `T`, since the columns of `R` are joined onto it
+/// by the [`FetchRef`](crate::cte::FetchRef) filter and follow the columns of `T`.
+impl<DB: Database, T, R> Deserializeable<DB> for Dereferenced<T, R>
+where
+    T: Deserializeable<DB>,
+    R: Deserializeable<DB>,
+{
+    fn cte() -> Box<dyn CommonTableExpression> {
+        <T as Deserializeable<DB>>::cte()
+    }
+
+    fn deserialize(row: &mut OffsetRow<<DB as Database>::Row>) -> Result<Self, sqlx::Error> {
+        Ok(Dereferenced {
+            components: <T as Deserializeable<DB>>::deserialize(row)?,
+            referenced: <R as Deserializeable<DB>>::deserialize(row)?,
+        })
+    }
+}
+
+/// Entity returned from a hierarchy query, along with its depth relative to the root entity.
+#[derive(Debug)]
+pub struct Related<EntityId, T> {
+    depth: i64,
+    entity: Entity<EntityId, T>,
+}
+
+impl<EntityId, T> Related<EntityId, T> {
+    /// Distance from the root entity, `1` being an immediate child or parent.
+    pub fn depth(&self) -> i64 {
+        self.depth
+    }
+
+    /// Borrow the related entity.
+    pub fn entity(&self) -> &Entity<EntityId, T> {
+        &self.entity
+    }
+
+    /// Consumes the relation, returning the related entity.
+    pub fn into_entity(self) -> Entity<EntityId, T> {
+        self.entity
+    }
+}
+
+impl<EntityId, T> Deref for Related<EntityId, T> {
+    type Target = Entity<EntityId, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entity
+    }
+}
+
+impl<'r, R, EntityId, T> FromRow<'r, R> for Related<EntityId, T>
+where
+    R: Row,
+    EntityId: for<'e> sqlx::Decode<'e, <R as sqlx::Row>::Database>
+        + sqlx::Type<<R as sqlx::Row>::Database>,
+    i64: for<'e> sqlx::Decode<'e, <R as sqlx::Row>::Database>
+        + sqlx::Type<<R as sqlx::Row>::Database>,
+    T: Deserializeable<<R as Row>::Database>,
+    usize: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let mut row = OffsetRow::new(row);
+        let id = row.try_get::<EntityId>()?;
+        let depth = row.try_get::<i64>()?;
+
+        Ok(Related {
+            depth,
+            entity: Entity {
+                id,
+                inner: <T as Deserializeable<<R as Row>::Database>>::deserialize(&mut row)?,
+            },
+        })
+    }
+}
@@ -1,3 +1,5 @@
+use std::future::Future;
+
 use sqlx::{query::Query, ColumnIndex, Database};
 
 use crate::{cte::*, entity::EntityPrefixedQuery, row::OffsetRow, tables::Removable};
@@ -24,6 +26,34 @@ pub trait Serializable<DB: Database>: Sized {
         query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
     ) where
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query;
+
+    /// Run the [`Hooks::on_insert`](crate::component::Hooks::on_insert) hooks of the contained components.
+    fn on_insert<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
+
+    /// Run the [`Hooks::on_update`](crate::component::Hooks::on_update) hooks of the contained components.
+    fn on_update<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
 }
 
 impl<T: Deserializeable<DB>, DB: Database> Deserializeable<DB> for Option<T>
@@ -36,16 +66,92 @@ where
         })
     }
 
+    /// The first column is the entity column of `T`, which is null if the entity has no `T`.
     fn deserialize(row: &mut OffsetRow<<DB as Database>::Row>) -> Result<Self, sqlx::Error> {
         if row.is_null() {
-            row.skip(1);
+            row.skip(1 + <T as Deserializeable<DB>>::cte().column_count());
             Ok(None)
         } else {
+            row.skip(1);
             <T as Deserializeable<DB>>::deserialize(row).map(Some)
         }
     }
 }
 
+/// Optional components are only written when present, so `None` leaves
+/// the entity's existing component, if any, untouched.
+impl<T: Serializable<DB> + Sync, DB: Database> Serializable<DB> for Option<T> {
+    fn serialize<'query>(
+        &'query self,
+        query: Query<'query, DB, <DB as Database>::Arguments<'query>>,
+    ) -> Query<'query, DB, <DB as Database>::Arguments<'query>> {
+        match self {
+            Some(inner) => inner.serialize(query),
+            None => query,
+        }
+    }
+
+    fn insert<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        if let Some(inner) = self {
+            inner.insert(query);
+        }
+    }
+
+    fn update<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        if let Some(inner) = self {
+            inner.update(query);
+        }
+    }
+
+    fn on_insert<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let hook = self
+            .as_ref()
+            .map(|inner| inner.on_insert(entity, connection));
+
+        async move {
+            match hook {
+                Some(hook) => hook.await,
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn on_update<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let hook = self
+            .as_ref()
+            .map(|inner| inner.on_update(entity, connection));
+
+        async move {
+            match hook {
+                Some(hook) => hook.await,
+                None => Ok(()),
+            }
+        }
+    }
+}
+
 impl<T: Removable<DB>, DB: Database> Removable<DB> for Option<T> {
     fn remove<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
     where
@@ -53,6 +159,16 @@ impl<T: Removable<DB>, DB: Database> Removable<DB> for Option<T> {
     {
         <T as Removable<DB>>::remove(query);
     }
+
+    fn on_remove<'hook, EntityId>(
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        <T as Removable<DB>>::on_remove(entity, connection)
+    }
 }
 
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
@@ -83,7 +199,7 @@ macro_rules! impl_deser_for_db{
 
         impl<$($list),*> Serializable<$db> for ($($list,)*)
         where
-            $($list: Serializable<$db>,)*
+            $($list: Serializable<$db> + Sync,)*
         {
             fn serialize<'q>(
                 &'q self,
@@ -125,6 +241,46 @@ macro_rules! impl_deser_for_db{
                     self.$index.update(query);
                 )*
             }
+
+            fn on_insert<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <$db as Database>::Connection,
+            ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> sqlx::Encode<'e, $db> + sqlx::Type<$db> + Clone + Send + 'hook,
+            {
+                async move {
+                    $(
+                        #[allow(unused)]
+                        const $list: () = ();
+                        self.$index.on_insert(entity.clone(), &mut *connection).await?;
+                    )*
+
+                    Ok(())
+                }
+            }
+
+            fn on_update<'hook, EntityId>(
+                &'hook self,
+                entity: EntityId,
+                connection: &'hook mut <$db as Database>::Connection,
+            ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+            where
+                Self: Sync,
+                EntityId: for<'e> sqlx::Encode<'e, $db> + sqlx::Type<$db> + Clone + Send + 'hook,
+            {
+                async move {
+                    $(
+                        #[allow(unused)]
+                        const $list: () = ();
+                        self.$index.on_update(entity.clone(), &mut *connection).await?;
+                    )*
+
+                    Ok(())
+                }
+            }
         }
     }
 }
@@ -140,11 +296,4 @@ macro_rules! impl_compound {
     };
 }
 
-impl_compound!(A:0, B:1);
-impl_compound!(A:0, B:1, C:2);
-impl_compound!(A:0, B:1, C:2, D:3);
-impl_compound!(A:0, B:1, C:2, D:3, E:4);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+for_tuples!(impl_compound);
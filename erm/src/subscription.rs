@@ -0,0 +1,139 @@
+//! Live notifications of changes to entities matching a [`List`](crate::backend::List) query.
+//!
+//! Change notifications are produced by triggers, which are created on the tables a query reads
+//! the first time it is subscribed to, and delivered using `LISTEN/NOTIFY` on Postgres and the
+//! update hook on Sqlite. Components which are never subscribed to do not pay for the triggers.
+//! Subscriptions are not available for MySQL.
+//!
+//! Notifications which are lost, because Sqlite only keeps the most recent events around for
+//! subscriptions to catch up on, or because the connection to Postgres dropped, are detected.
+//! The subscription then evaluates its query again, reporting every matching entity as updated,
+//! or inserted if it did not match before, and entities which no longer match as removed.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use futures::{channel::mpsc::UnboundedSender, Stream};
+use sqlx::{Database, Pool};
+
+use crate::{cte::CommonTableExpression, row::Entity};
+
+/// Kind of change made to a component table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    pub(crate) fn parse(operation: &str) -> Option<Self> {
+        match operation {
+            "insert" => Some(Operation::Insert),
+            "update" => Some(Operation::Update),
+            "delete" => Some(Operation::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Change to an entity, as observed by a subscription.
+///
+/// Since every component table notifies separately, a single call to
+/// `insert` or `update` may produce more than one event for the same entity.
+#[derive(Debug)]
+pub enum Event<EntityId, T> {
+    /// An entity which did not match the query before the change now matches it.
+    Inserted(Entity<EntityId, T>),
+    /// A component of an entity matching the query changed, and the entity still matches it.
+    Updated(Entity<EntityId, T>),
+    /// An entity which matched the query before the change no longer matches it.
+    Removed(EntityId),
+}
+
+/// Change notification for a single row of a component table.
+pub struct Notification<EntityId> {
+    pub operation: Operation,
+    pub table: String,
+    pub entity: EntityId,
+}
+
+/// Item of the stream returned by [`Notify::notifications`].
+pub enum Received<EntityId> {
+    /// A row of a component table changed.
+    Notification(Notification<EntityId>),
+    /// Notifications were lost before they could be delivered, so subscribers
+    /// must evaluate their query again to catch up.
+    Missed,
+}
+
+/// Databases capable of notifying subscribers of changes to component tables.
+pub trait Notify: Database {
+    /// Create the triggers required for notifying subscribers of changes to `table`.
+    fn create_triggers<EntityId>(
+        pool: &Pool<Self>,
+        table: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send
+    where
+        EntityId: sqlx::Type<Self>;
+
+    /// Start listening for changes made to component tables, streaming all changes
+    /// made after the returned future resolves, or [`Received::Missed`] when some of
+    /// them were lost.
+    fn notifications<EntityId>(
+        pool: Pool<Self>,
+        notifier: Notifier,
+    ) -> impl Future<
+        Output = Result<
+            impl Stream<Item = Result<Received<EntityId>, sqlx::Error>> + Send,
+            sqlx::Error,
+        >,
+    > + Send
+    where
+        EntityId: for<'r> sqlx::Decode<'r, Self> + sqlx::Type<Self> + Unpin + Send + 'static;
+}
+
+/// Wakes up subscriptions when the database reports a change.
+///
+/// Only used by backends relying on per-connection hooks, such as Sqlite.
+#[derive(Debug, Default, Clone)]
+pub struct Notifier(
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))] Arc<Mutex<Vec<UnboundedSender<()>>>>,
+);
+
+#[cfg(feature = "sqlite")]
+impl Notifier {
+    /// Register a new subscriber.
+    pub(crate) fn subscribe(&self) -> futures::channel::mpsc::UnboundedReceiver<()> {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        self.0.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Wake all subscribers, dropping the ones which have gone away.
+    pub(crate) fn notify(&self) {
+        self.0
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.unbounded_send(()).is_ok());
+    }
+}
+
+/// Collects the names of all component tables read by the expression, including the
+/// child tables of collections, without duplicates.
+pub(crate) fn tables(cte: &dyn CommonTableExpression, tables: &mut Vec<&'static str>) {
+    let mut sources = Vec::new();
+    cte.source_tables(&mut sources);
+
+    for source in sources {
+        if !tables.contains(&source) {
+            tables.push(source);
+        }
+    }
+
+    for dependency in cte.dependencies() {
+        self::tables(dependency.as_ref(), tables);
+    }
+}
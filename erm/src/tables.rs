@@ -1,3 +1,5 @@
+use std::future::Future;
+
 use sqlx::Database;
 
 use crate::entity::EntityPrefixedQuery;
@@ -6,6 +8,18 @@ pub trait Removable<DB: Database>: Sized {
     fn remove<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
     where
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query;
+
+    /// Run the [`Hooks::on_remove`](crate::component::Hooks::on_remove) hooks of the contained components.
+    fn on_remove<'hook, EntityId>(
+        entity: EntityId,
+        connection: &'hook mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+    where
+        EntityId: for<'e> sqlx::Encode<'e, DB> + sqlx::Type<DB> + Clone + Send + 'hook,
+    {
+        let _ = (entity, connection);
+        async { Ok(()) }
+    }
 }
 
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
@@ -27,6 +41,22 @@ macro_rules! impl_remove_for_db{
                     }
                 )*
             }
+
+            fn on_remove<'hook, EntityId>(
+                entity: EntityId,
+                connection: &'hook mut <$db as Database>::Connection,
+            ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'hook
+            where
+                EntityId: for<'e> sqlx::Encode<'e, $db> + sqlx::Type<$db> + Clone + Send + 'hook,
+            {
+                async move {
+                    $(
+                        <$list as Removable<$db>>::on_remove(entity.clone(), &mut *connection).await?;
+                    )*
+
+                    Ok(())
+                }
+            }
         }
     };
 }
@@ -42,11 +72,4 @@ macro_rules! impl_compound {
     };
 }
 
-impl_compound!(A:0, B:1);
-impl_compound!(A:0, B:1, C:2);
-impl_compound!(A:0, B:1, C:2, D:3);
-impl_compound!(A:0, B:1, C:2, D:3, E:4);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
-impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+for_tuples!(impl_compound);
@@ -0,0 +1,139 @@
+//! Optimistic concurrency control for components deriving `#[erm(versioned)]`.
+//!
+//! Such components have a `version` column, starting at `1` and incremented by every update.
+//! Reading a component as [`Version<T>`] returns its version alongside it, and
+//! [`Backend::update_versioned`](crate::backend::Backend::update_versioned) only applies
+//! the update if the component is still at that version.
+
+use std::ops::{Deref, DerefMut};
+
+use sqlx::{ColumnIndex, Database};
+
+use crate::{
+    component::Versioned, cte::CommonTableExpression, row::OffsetRow,
+    serialization::Deserializeable,
+};
+
+/// Database-specific access to the number of rows affected by a statement.
+pub trait RowsAffected: Database {
+    fn rows_affected(result: &<Self as Database>::QueryResult) -> u64;
+}
+
+#[cfg(feature = "sqlite")]
+impl RowsAffected for sqlx::Sqlite {
+    fn rows_affected(result: &<Self as Database>::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl RowsAffected for sqlx::Postgres {
+    fn rows_affected(result: &<Self as Database>::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl RowsAffected for sqlx::MySql {
+    fn rows_affected(result: &<Self as Database>::QueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+/// Component read along with its version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version<T> {
+    version: i64,
+    component: T,
+}
+
+impl<T> Version<T> {
+    /// Construct a component at a known version.
+    pub fn new(version: i64, component: T) -> Self {
+        Version { version, component }
+    }
+
+    /// Version of the component at the time it was read.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Borrow the component.
+    pub fn component(&self) -> &T {
+        &self.component
+    }
+
+    /// Consumes the version, returning the component.
+    pub fn into_component(self) -> T {
+        self.component
+    }
+}
+
+impl<T> Deref for Version<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.component
+    }
+}
+
+impl<T> DerefMut for Version<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.component
+    }
+}
+
+impl<T, DB> Deserializeable<DB> for Version<T>
+where
+    DB: Database,
+    T: Versioned<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+{
+    fn cte() -> Box<dyn CommonTableExpression> {
+        <T as Deserializeable<DB>>::cte()
+    }
+
+    /// The version is the last column of the component, which it skips when deserializing,
+    /// so it is read by position rather than by moving the offset back.
+    fn deserialize(row: &mut OffsetRow<<DB as Database>::Row>) -> Result<Self, sqlx::Error> {
+        let position = row.offset + <T as Deserializeable<DB>>::cte().column_count() - 1;
+
+        let component = <T as Deserializeable<DB>>::deserialize(row)?;
+        let version = sqlx::Row::try_get::<i64, usize>(row.row, position)?;
+
+        Ok(Version { version, component })
+    }
+}
+
+/// Error returned by [`Backend::update_versioned`](crate::backend::Backend::update_versioned).
+#[derive(Debug)]
+pub enum UpdateError {
+    /// The component was updated or removed since the expected version was read.
+    Conflict,
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Conflict => write!(f, "component was modified since it was read"),
+            UpdateError::Database(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpdateError::Conflict => None,
+            UpdateError::Database(error) => Some(error),
+        }
+    }
+}
+
+impl From<sqlx::Error> for UpdateError {
+    fn from(error: sqlx::Error) -> Self {
+        UpdateError::Database(error)
+    }
+}
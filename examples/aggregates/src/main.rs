@@ -0,0 +1,93 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Faction(String);
+
+#[derive(Component, Debug)]
+pub struct Inventory {
+    gold: i64,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Faction>().await.unwrap();
+    backend.register::<Inventory>().await.unwrap();
+
+    backend
+        .insert(&1, &(Faction("Elves".to_string()), Inventory { gold: 100 }))
+        .await
+        .unwrap();
+
+    backend
+        .insert(&2, &(Faction("Elves".to_string()), Inventory { gold: 50 }))
+        .await
+        .unwrap();
+
+    backend
+        .insert(
+            &3,
+            &(Faction("Dwarves".to_string()), Inventory { gold: 1000 }),
+        )
+        .await
+        .unwrap();
+
+    // Total amount of gold across all inventories.
+    let total = backend
+        .list::<Inventory>()
+        .sum(Inventory::FIELDS.gold)
+        .await
+        .unwrap();
+
+    println!("total: {total:?}");
+    // total: Some(1150)
+    assert_eq!(total, Some(1150));
+
+    // Number of entities with more than 75 gold.
+    let wealthy = backend
+        .list::<Inventory>()
+        .filter(Inventory::FIELDS.gold.greater_than(75))
+        .count()
+        .await
+        .unwrap();
+
+    assert_eq!(wealthy, 2);
+
+    // Average gold held by each faction.
+    let mut per_faction = backend
+        .list::<(Faction, Inventory)>()
+        .group_by(Faction::FIELDS.self_0)
+        .avg(Inventory::FIELDS.gold)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    per_faction.sort_by(|a, b| a.0.cmp(&b.0));
+
+    println!("{per_faction:#?}");
+    // [
+    //     (
+    //         "Dwarves",
+    //         Some(
+    //             1000.0,
+    //         ),
+    //     ),
+    //     (
+    //         "Elves",
+    //         Some(
+    //             75.0,
+    //         ),
+    //     ),
+    // ]
+    assert_eq!(
+        per_faction,
+        vec![
+            ("Dwarves".to_string(), Some(1000.0)),
+            ("Elves".to_string(), Some(75.0))
+        ]
+    );
+}
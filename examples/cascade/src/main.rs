@@ -0,0 +1,80 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+// Children are despawned along with the entity they belong to.
+#[derive(Component, Debug)]
+pub struct BelongsTo(#[erm(references = Name, cascade)] EntityRef<i64>);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // Referenced tables must be registered before the tables referencing them.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<BelongsTo>().await.unwrap();
+
+    let guild = 1;
+    backend
+        .insert(&guild, &Name("Guild".to_string()))
+        .await
+        .unwrap();
+
+    let member = 2;
+    backend
+        .insert(
+            &member,
+            &(Name("Member".to_string()), BelongsTo(EntityRef(guild))),
+        )
+        .await
+        .unwrap();
+
+    // The member's pet belongs to the member, and so indirectly to the guild.
+    let pet = 3;
+    backend
+        .insert(
+            &pet,
+            &(Name("Pet".to_string()), BelongsTo(EntityRef(member))),
+        )
+        .await
+        .unwrap();
+
+    let loner = 4;
+    backend
+        .insert(&loner, &Name("Loner".to_string()))
+        .await
+        .unwrap();
+
+    // Despawning the guild takes its member, and the member's pet with it.
+    backend.despawn(&guild).await.unwrap();
+
+    let remaining = backend
+        .list::<Name>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{remaining:#?}");
+    // [
+    //     Name(
+    //         "Loner",
+    //     ),
+    // ]
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].0, "Loner");
+
+    let memberships = backend
+        .list::<BelongsTo>()
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert!(memberships.is_empty());
+}
@@ -0,0 +1,126 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+// Position tracks when each row was added and last changed.
+#[derive(Component, Debug, PartialEq)]
+#[erm(track_changes)]
+pub struct Position {
+    x: i64,
+    y: i64,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Position>().await.unwrap();
+
+    let player = 1;
+    backend
+        .insert(
+            &player,
+            &(Name("Player".to_string()), Position { x: 0, y: 0 }),
+        )
+        .await
+        .unwrap();
+
+    let monster = 2;
+    backend
+        .insert(
+            &monster,
+            &(Name("Monster".to_string()), Position { x: 10, y: 10 }),
+        )
+        .await
+        .unwrap();
+
+    // Remember the latest tick the client has seen.
+    let synced = backend
+        .list::<Position>()
+        .max(Position::FIELDS.changed_at)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The player moves, and a new tree is planted.
+    backend
+        .update(&player, &Position { x: 1, y: 0 })
+        .await
+        .unwrap();
+
+    let tree = 3;
+    backend
+        .insert(&tree, &(Name("Tree".to_string()), Position { x: 5, y: 5 }))
+        .await
+        .unwrap();
+
+    // Only the player and the tree need to be sent to the client.
+    let changed = backend
+        .list::<Name>()
+        .changed::<Position>(synced)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{changed:#?}");
+    // [
+    //     Name(
+    //         "Player",
+    //     ),
+    //     Name(
+    //         "Tree",
+    //     ),
+    // ]
+    assert_eq!(changed.len(), 2);
+
+    // Of which only the tree is new.
+    let added = backend
+        .list::<Name>()
+        .added::<Position>(synced)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].0, "Tree");
+
+    assert_eq!(
+        backend.get::<Position>(&player).await.unwrap(),
+        Position { x: 1, y: 0 }
+    );
+
+    // Ticks are never reused, even once the row holding the latest one is removed.
+    let synced = backend
+        .list::<Position>()
+        .max(Position::FIELDS.changed_at)
+        .await
+        .unwrap()
+        .unwrap();
+
+    backend.remove::<Position>(&tree).await.unwrap();
+    backend
+        .update(&monster, &Position { x: 9, y: 10 })
+        .await
+        .unwrap();
+
+    let changed = backend
+        .list::<Name>()
+        .changed::<Position>(synced)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].0, "Monster");
+}
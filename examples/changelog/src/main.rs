@@ -0,0 +1,108 @@
+use erm::prelude::*;
+use erm::subscription::Operation;
+use futures::TryStreamExt as _;
+
+// Every change to a player's gold is recorded in the changelog.
+#[derive(Component, Debug, PartialEq)]
+#[erm(changelog)]
+pub struct Inventory {
+    owner: String,
+    gold: i64,
+}
+
+// Binary columns are recorded as well.
+#[derive(Component, Debug, PartialEq)]
+#[erm(changelog)]
+pub struct Avatar(Vec<u8>);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component table, as well as the erm_changelog table.
+    backend.register::<Inventory>().await.unwrap();
+    backend.register::<Avatar>().await.unwrap();
+
+    let player = 1;
+    backend
+        .insert(
+            &player,
+            &Inventory {
+                owner: "Player".to_string(),
+                gold: 10,
+            },
+        )
+        .await
+        .unwrap();
+
+    backend
+        .update(
+            &player,
+            &Inventory {
+                owner: "Player".to_string(),
+                gold: 25,
+            },
+        )
+        .await
+        .unwrap();
+
+    backend.remove::<Inventory>(&player).await.unwrap();
+
+    // Read back the history of the component.
+    let changes = backend
+        .changelog::<Inventory>(0)
+        .map_ok(|change| (change.operation(), change.into_component().gold))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{changes:#?}");
+    // [
+    //     (
+    //         Insert,
+    //         10,
+    //     ),
+    //     (
+    //         Update,
+    //         25,
+    //     ),
+    //     (
+    //         Delete,
+    //         25,
+    //     ),
+    // ]
+    assert_eq!(
+        changes,
+        vec![
+            (Operation::Insert, 10),
+            (Operation::Update, 25),
+            (Operation::Delete, 25)
+        ]
+    );
+
+    // Clients which have already seen the first change can skip it.
+    let missed = backend
+        .changelog::<Inventory>(1)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(missed.len(), 2);
+
+    backend
+        .insert(&player, &Avatar(vec![0, 1, 2]))
+        .await
+        .unwrap();
+
+    let avatars = backend
+        .changelog::<Avatar>(0)
+        .map_ok(|change| change.into_component())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{avatars:?}");
+    // [Avatar([0, 1, 2])]
+    assert_eq!(avatars, vec![Avatar(vec![0, 1, 2])]);
+}
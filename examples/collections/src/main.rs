@@ -0,0 +1,118 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+use sqlx::sqlite::SqlitePoolOptions;
+
+// Tags are stored in the `Article_tags` table, one row per tag.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Article {
+    title: String,
+    #[erm(collection)]
+    tags: Vec<String>,
+}
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Author(String);
+
+#[tokio::main]
+async fn main() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let backend: SqliteBackend<i64> = SqliteBackend::new(pool.clone());
+    backend.register::<Article>().await.unwrap();
+    backend.register::<Author>().await.unwrap();
+
+    let intro = 1;
+    backend
+        .insert(
+            &intro,
+            &Article {
+                title: "Introduction".to_string(),
+                tags: vec!["rust".to_string(), "databases".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+
+    let draft = 2;
+    backend
+        .insert(
+            &draft,
+            &Article {
+                title: "Draft".to_string(),
+                tags: vec![],
+            },
+        )
+        .await
+        .unwrap();
+
+    // Elements keep their order.
+    let article = backend.get::<Article>(&intro).await.unwrap();
+    assert_eq!(article.tags, vec!["rust", "databases"]);
+
+    let draft_article = backend.get::<Article>(&draft).await.unwrap();
+    assert!(draft_article.tags.is_empty());
+
+    // Updates replace the entire collection.
+    let mut article = article;
+    article.tags = vec![
+        "databases".to_string(),
+        "sql".to_string(),
+        "rust".to_string(),
+    ];
+    backend.update(&intro, &article).await.unwrap();
+
+    assert_eq!(backend.get::<Article>(&intro).await.unwrap(), article);
+
+    // Entities can be filtered by the elements of their collections.
+    let about_sql = backend
+        .list::<Article>()
+        .filter(Article::FIELDS.tags.contains("sql".to_string()))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{about_sql:#?}");
+    // [
+    //     1,
+    // ]
+    assert_eq!(about_sql, vec![intro]);
+
+    // Collections can be filtered on when queried along with other components.
+    backend
+        .insert(&intro, &Author("Ada".to_string()))
+        .await
+        .unwrap();
+    backend
+        .insert(&draft, &Author("Grace".to_string()))
+        .await
+        .unwrap();
+
+    let authors = backend
+        .list::<(Author, Article)>()
+        .filter(Article::FIELDS.tags.contains("rust".to_string()))
+        .components()
+        .fetch()
+        .map_ok(|(author, _)| author)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(authors, vec![Author("Ada".to_string())]);
+
+    // Removing the component removes its elements.
+    backend.remove::<Article>(&intro).await.unwrap();
+
+    let tags: i64 = sqlx::query_scalar("select count(*) from Article_tags")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(tags, 0);
+}
@@ -23,12 +23,14 @@ async fn main() {
     let jimothy = 1;
     backend
         .insert(&jimothy, &(Name("Jimothy".to_string()), Age(10)))
-        .await;
+        .await
+        .unwrap();
 
     let andrea = 2;
     backend
         .insert(&andrea, &(Name("Andrea".to_string()), Age(32)))
-        .await;
+        .await
+        .unwrap();
 
     // Let's name an Archetype instead of just relying on a tuple.
     #[derive(Archetype, Debug)]
@@ -38,10 +40,13 @@ async fn main() {
         age: Age,
     }
 
+    // Conditions on reflected columns can be declared as constants.
+    const ADULT: condition::GreaterThanOrEqual<i64> = Age::FIELDS.self_0.greater_than_or_equals(18);
+
     // List all adult IDs
     let people = backend
         .list::<Person>()
-        .filter(Age::FIELDS.self_0.greater_than_or_equals(18))
+        .filter(ADULT)
         .ids()
         .fetch()
         .try_collect::<Vec<_>>()
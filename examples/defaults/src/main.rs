@@ -0,0 +1,74 @@
+use erm::prelude::*;
+use sqlx::sqlite::SqlitePoolOptions;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Player {
+    name: String,
+    // Added in a later version, so older rows hold null instead.
+    #[erm(default = "100")]
+    health: i64,
+    // Only kept in memory, and reset when loaded.
+    #[erm(skip)]
+    visible: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    // A player saved before the health column was added to the table.
+    sqlx::query("create table Player(entity integer primary key, name text not null)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("insert into Player(entity, name) values (1, 'Veteran')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("alter table Player add column health integer null")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let backend: SqliteBackend<i64> = SqliteBackend::new(pool);
+    backend.register::<Player>().await.unwrap();
+
+    let veteran = backend.get::<Player>(&1).await.unwrap();
+    assert_eq!(
+        veteran,
+        Player {
+            name: "Veteran".to_string(),
+            health: 100,
+            visible: false,
+        }
+    );
+
+    let rookie = 2;
+    backend
+        .insert(
+            &rookie,
+            &Player {
+                name: "Rookie".to_string(),
+                health: 80,
+                visible: true,
+            },
+        )
+        .await
+        .unwrap();
+
+    let rookie = backend.get::<Player>(&rookie).await.unwrap();
+    println!("{rookie:#?}");
+    // Player {
+    //     name: "Rookie",
+    //     health: 80,
+    //     visible: false,
+    // }
+    assert_eq!(rookie.health, 80);
+    assert!(!rookie.visible);
+}
@@ -0,0 +1,125 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Unit-only enums are stored as the name of the variant.
+#[derive(Component, Debug, PartialEq)]
+pub enum Faction {
+    Neutral,
+    Hostile,
+}
+
+// Variants with fields get a nullable column per field, such as chasing_target,
+// which is only set while the entity is in that variant. Optional fields are
+// stored as nullable columns of their inner type, such as wandering_towards.
+#[derive(Component, Debug, PartialEq)]
+pub enum AiState {
+    Idle,
+    Chasing { target: i64 },
+    Fleeing(i64, f64),
+    Wandering { towards: Option<i64> },
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Faction>().await.unwrap();
+    backend.register::<AiState>().await.unwrap();
+
+    let goblin = 1;
+    backend
+        .insert(
+            &goblin,
+            &(Name("Goblin".to_string()), Faction::Hostile, AiState::Idle),
+        )
+        .await
+        .unwrap();
+
+    let rabbit = 2;
+    backend
+        .insert(
+            &rabbit,
+            &(
+                Name("Rabbit".to_string()),
+                Faction::Neutral,
+                AiState::Fleeing(goblin, 2.5),
+            ),
+        )
+        .await
+        .unwrap();
+
+    // The goblin spots the rabbit.
+    backend
+        .update(&goblin, &AiState::Chasing { target: rabbit })
+        .await
+        .unwrap();
+
+    let creatures = backend
+        .list::<(Name, Faction, AiState)>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{creatures:#?}");
+    // [
+    //     (
+    //         Name(
+    //             "Goblin",
+    //         ),
+    //         Hostile,
+    //         Chasing {
+    //             target: 2,
+    //         },
+    //     ),
+    //     (
+    //         Name(
+    //             "Rabbit",
+    //         ),
+    //         Neutral,
+    //         Fleeing(
+    //             1,
+    //             2.5,
+    //         ),
+    //     ),
+    // ]
+    assert_eq!(
+        creatures,
+        vec![
+            (
+                Name("Goblin".to_string()),
+                Faction::Hostile,
+                AiState::Chasing { target: rabbit },
+            ),
+            (
+                Name("Rabbit".to_string()),
+                Faction::Neutral,
+                AiState::Fleeing(goblin, 2.5),
+            ),
+        ]
+    );
+
+    // The rabbit got away.
+    backend.update(&goblin, &AiState::Idle).await.unwrap();
+    assert_eq!(
+        backend.get::<AiState>(&goblin).await.unwrap(),
+        AiState::Idle
+    );
+
+    // Both the absence and presence of an optional field are read back as written.
+    for state in [
+        AiState::Wandering { towards: None },
+        AiState::Wandering {
+            towards: Some(rabbit),
+        },
+    ] {
+        backend.update(&goblin, &state).await.unwrap();
+        assert_eq!(backend.get::<AiState>(&goblin).await.unwrap(), state);
+    }
+}
@@ -0,0 +1,99 @@
+use erm::prelude::*;
+use erm::version::Version;
+use futures::TryStreamExt as _;
+
+#[derive(Flatten, Debug, Clone, PartialEq)]
+pub struct Vec3 {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+// Stored in the columns `label, pos_x, pos_y, pos_z, scale_x, scale_y, scale_z`.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[erm(versioned)]
+pub struct Transform {
+    label: String,
+    #[erm(flatten)]
+    pos: Vec3,
+    #[erm(flatten)]
+    scale: Vec3,
+}
+
+#[tokio::main]
+async fn main() {
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Transform>().await.unwrap();
+
+    let unit = Vec3 {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+
+    let player = 1;
+    backend
+        .insert(
+            &player,
+            &Transform {
+                label: "player".to_string(),
+                pos: Vec3 {
+                    x: 0.0,
+                    y: 10.0,
+                    z: 0.0,
+                },
+                scale: unit.clone(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let tree = 2;
+    backend
+        .insert(
+            &tree,
+            &Transform {
+                label: "tree".to_string(),
+                pos: Vec3 {
+                    x: 5.0,
+                    y: 0.0,
+                    z: -3.0,
+                },
+                scale: Vec3 {
+                    x: 2.0,
+                    y: 8.0,
+                    z: 2.0,
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+    let transform = backend.get::<Transform>(&tree).await.unwrap();
+    assert_eq!(transform.scale.y, 8.0);
+
+    // Nested columns can be filtered like any other.
+    let airborne = backend
+        .list::<Transform>()
+        .filter(Transform::FIELDS.pos.y.gt(1.0))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{airborne:#?}");
+    // [
+    //     1,
+    // ]
+    assert_eq!(airborne, vec![player]);
+
+    // Versioned updates bind the expected version after the flattened columns.
+    let mut current = backend.get::<Version<Transform>>(&player).await.unwrap();
+    current.pos.y = 0.0;
+    backend.update_versioned(&player, &current).await.unwrap();
+
+    let transform = backend.get::<Transform>(&player).await.unwrap();
+    assert_eq!(transform.pos.y, 0.0);
+    assert_eq!(transform.scale, unit);
+}
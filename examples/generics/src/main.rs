@@ -0,0 +1,106 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Each instantiation gets its own table, named Stat_i64 and Stat_f64 by default.
+#[derive(Component, Debug, PartialEq)]
+pub struct Stat<T> {
+    value: T,
+}
+
+// The table name can also be given as a template, referring to the type parameters.
+#[derive(Component, Debug, PartialEq)]
+#[erm(table = "tagged_{T}", track_changes)]
+pub struct Tagged<T>
+where
+    T: Clone,
+{
+    tag: String,
+    value: T,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Stat<i64>>().await.unwrap();
+    backend.register::<Stat<f64>>().await.unwrap();
+    backend.register::<Tagged<String>>().await.unwrap();
+
+    assert_eq!(<Stat<i64> as Component<sqlx::Sqlite>>::table(), "Stat_i64");
+    assert_eq!(
+        <Tagged<String> as Component<sqlx::Sqlite>>::table(),
+        "tagged_String"
+    );
+
+    let knight = 1;
+    backend
+        .insert(
+            &knight,
+            &(
+                Name("Knight".to_string()),
+                Stat { value: 12i64 },
+                Stat { value: 0.5f64 },
+                Tagged {
+                    tag: "class".to_string(),
+                    value: "melee".to_string(),
+                },
+            ),
+        )
+        .await
+        .unwrap();
+
+    let archer = 2;
+    backend
+        .insert(
+            &archer,
+            &(
+                Name("Archer".to_string()),
+                Stat { value: 8i64 },
+                Stat { value: 0.9f64 },
+            ),
+        )
+        .await
+        .unwrap();
+
+    backend
+        .update(&archer, &Stat { value: 10i64 })
+        .await
+        .unwrap();
+
+    // Reflection works the same as for any other component.
+    let strong = backend
+        .list::<(Name, Stat<i64>, Stat<f64>)>()
+        .filter(Stat::<i64>::FIELDS.value.greater_than(9))
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{strong:#?}");
+    assert_eq!(strong.len(), 2);
+    assert_eq!(strong[1].2, Stat { value: 0.9 });
+
+    let tagged = backend
+        .list::<(Name, Tagged<String>)>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        tagged,
+        vec![(
+            Name("Knight".to_string()),
+            Tagged {
+                tag: "class".to_string(),
+                value: "melee".to_string(),
+            }
+        )]
+    );
+}
@@ -0,0 +1,132 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+// Each node in the scene graph points to its parent.
+#[derive(Component, Debug)]
+pub struct Parent(EntityRef<i64>);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Parent>().await.unwrap();
+
+    // world
+    // └── house
+    //     ├── table
+    //     │   └── cup
+    //     └── chair
+    let world = 1;
+    backend
+        .insert(&world, &Name("world".to_string()))
+        .await
+        .unwrap();
+
+    let house = 2;
+    backend
+        .insert(
+            &house,
+            &(Name("house".to_string()), Parent(EntityRef(world))),
+        )
+        .await
+        .unwrap();
+
+    let table = 3;
+    backend
+        .insert(
+            &table,
+            &(Name("table".to_string()), Parent(EntityRef(house))),
+        )
+        .await
+        .unwrap();
+
+    let cup = 4;
+    backend
+        .insert(&cup, &(Name("cup".to_string()), Parent(EntityRef(table))))
+        .await
+        .unwrap();
+
+    let chair = 5;
+    backend
+        .insert(
+            &chair,
+            &(Name("chair".to_string()), Parent(EntityRef(house))),
+        )
+        .await
+        .unwrap();
+
+    // Everything contained within the house, and how deeply nested it is.
+    let mut contents = backend
+        .descendants_of::<Parent, Name>(Parent::FIELDS.self_0, &house)
+        .map_ok(|related| (related.depth(), related.into_entity().into_components().0))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    contents.sort();
+
+    println!("{contents:#?}");
+    // [
+    //     (
+    //         1,
+    //         "chair",
+    //     ),
+    //     (
+    //         1,
+    //         "table",
+    //     ),
+    //     (
+    //         2,
+    //         "cup",
+    //     ),
+    // ]
+    assert_eq!(
+        contents,
+        vec![
+            (1, "chair".to_string()),
+            (1, "table".to_string()),
+            (2, "cup".to_string())
+        ]
+    );
+
+    // Path from the cup to the root of the scene graph.
+    let mut path = backend
+        .ancestors_of::<Parent, Name>(Parent::FIELDS.self_0, &cup)
+        .map_ok(|related| (related.depth(), *related.id()))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    path.sort();
+
+    assert_eq!(path, vec![(1, table), (2, house), (3, world)]);
+
+    // Cycles are only walked once, so entities within them are their own descendants.
+    let ping = 6;
+    let pong = 7;
+    backend
+        .insert(&ping, &(Name("ping".to_string()), Parent(EntityRef(pong))))
+        .await
+        .unwrap();
+    backend
+        .insert(&pong, &(Name("pong".to_string()), Parent(EntityRef(ping))))
+        .await
+        .unwrap();
+
+    let mut cycle = backend
+        .descendants_of::<Parent, Name>(Parent::FIELDS.self_0, &ping)
+        .map_ok(|related| (related.depth(), *related.id()))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    cycle.sort();
+
+    assert_eq!(cycle, vec![(1, pong), (2, ping)]);
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Every version of a Position is kept, so we can look back in time.
+#[derive(Component, Debug, PartialEq)]
+#[erm(history)]
+pub struct Position {
+    x: i64,
+    y: i64,
+}
+
+/// Current time in milliseconds since the unix epoch.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables, as well as the Position_history table.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Position>().await.unwrap();
+
+    let before = now();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let player = 1;
+    backend
+        .insert(
+            &player,
+            &(Name("Player".to_string()), Position { x: 0, y: 0 }),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let start = now();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    backend
+        .update(&player, &Position { x: 5, y: 3 })
+        .await
+        .unwrap();
+
+    // Where was the player when the game started?
+    let positions = backend
+        .list::<(Name, Position)>()
+        .as_of(start)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{positions:#?}");
+    // [
+    //     (
+    //         Name(
+    //             "Player",
+    //         ),
+    //         Position {
+    //             x: 0,
+    //             y: 0,
+    //         },
+    //     ),
+    // ]
+    assert_eq!(
+        positions,
+        vec![(Name("Player".to_string()), Position { x: 0, y: 0 })]
+    );
+
+    // The current position is unaffected.
+    let current = backend.get::<Position>(&player).await.unwrap();
+    assert_eq!(current, Position { x: 5, y: 3 });
+
+    // Before the player was inserted, there was no position to find.
+    let positions = backend
+        .list::<Position>()
+        .as_of(before)
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert!(positions.is_empty());
+
+    // Despawning the player ends the validity of its last position, which stays in the past.
+    backend.despawn(&player).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let positions = backend
+        .list::<Position>()
+        .as_of(now())
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert!(positions.is_empty());
+
+    let positions = backend
+        .list::<Position>()
+        .as_of(start)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(positions, vec![Position { x: 0, y: 0 }]);
+}
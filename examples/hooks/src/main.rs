@@ -0,0 +1,129 @@
+use erm::prelude::*;
+use sqlx::{Sqlite, SqliteConnection};
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct MaxHealth(i64);
+
+// Health runs its hooks whenever it is inserted, updated or removed.
+#[derive(Component, Debug, PartialEq)]
+#[erm(hooks)]
+pub struct Health(i64);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Dead;
+
+impl Health {
+    // Health must never exceed MaxHealth, so clamp it within the same transaction.
+    async fn clamp<EntityId>(
+        entity: EntityId,
+        connection: &mut SqliteConnection,
+    ) -> Result<(), sqlx::Error>
+    where
+        EntityId: for<'e> sqlx::Encode<'e, Sqlite> + sqlx::Type<Sqlite> + Clone + Send,
+    {
+        sqlx::query(
+            "update Health set column0 = min(column0, (select column0 from MaxHealth where entity = ?1)) where entity = ?1",
+        )
+        .bind(entity)
+        .execute(connection)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl Hooks<Sqlite> for Health {
+    async fn on_insert<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut SqliteConnection,
+    ) -> Result<(), sqlx::Error>
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, Sqlite> + sqlx::Type<Sqlite> + Clone + Send + 'hook,
+    {
+        Health::clamp(entity, connection).await
+    }
+
+    async fn on_update<'hook, EntityId>(
+        &'hook self,
+        entity: EntityId,
+        connection: &'hook mut SqliteConnection,
+    ) -> Result<(), sqlx::Error>
+    where
+        Self: Sync,
+        EntityId: for<'e> sqlx::Encode<'e, Sqlite> + sqlx::Type<Sqlite> + Clone + Send + 'hook,
+    {
+        Health::clamp(entity, connection).await
+    }
+
+    // Entities losing their health are marked as dead.
+    async fn on_remove<'hook, EntityId>(
+        entity: EntityId,
+        connection: &'hook mut SqliteConnection,
+    ) -> Result<(), sqlx::Error>
+    where
+        EntityId: for<'e> sqlx::Encode<'e, Sqlite> + sqlx::Type<Sqlite> + Clone + Send + 'hook,
+    {
+        sqlx::query("insert into Dead(entity) values(?1)")
+            .bind(entity)
+            .execute(connection)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<MaxHealth>().await.unwrap();
+    backend.register::<Health>().await.unwrap();
+    backend.register::<Dead>().await.unwrap();
+
+    let player = 1;
+    backend
+        .insert(&player, &(MaxHealth(100), Health(150)))
+        .await
+        .unwrap();
+
+    // The player was inserted with too much health, so it was clamped.
+    let health = backend.get::<Health>(&player).await.unwrap();
+    println!("{health:?}");
+    // Health(100)
+    assert_eq!(health, Health(100));
+
+    // Healing the player can't take them above their maximum either.
+    backend.update(&player, &Health(80)).await.unwrap();
+    backend.update(&player, &Health(120)).await.unwrap();
+
+    let health = backend.get::<Health>(&player).await.unwrap();
+    println!("{health:?}");
+    // Health(100)
+    assert_eq!(health, Health(100));
+
+    // Without a maximum, clamping fails, which rolls back the whole insert.
+    let monster = 2;
+    let result = backend
+        .insert(&monster, &(Name("Monster".to_string()), Health(50)))
+        .await;
+    println!("{}", result.is_err());
+    // true
+    assert!(result.is_err());
+    assert!(backend.get::<Name>(&monster).await.is_err());
+
+    // Removing the player's health kills them.
+    backend.remove::<Health>(&player).await.unwrap();
+
+    let dead = backend.get::<Dead>(&player).await.unwrap();
+    println!("{dead:?}");
+    // Dead
+    assert_eq!(dead, Dead);
+}
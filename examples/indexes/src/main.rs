@@ -0,0 +1,107 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+use sqlx::sqlite::SqlitePoolOptions;
+
+// Creates the indexes `Person_age_index`, `Person_email_unique`
+// and `Person_first_name_last_name_unique`.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[erm(unique(fields = [first_name, last_name]))]
+pub struct Person {
+    first_name: String,
+    last_name: String,
+    #[erm(index)]
+    age: i64,
+    #[erm(unique)]
+    email: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let backend: SqliteBackend<i64> = SqliteBackend::new(pool.clone());
+    backend.register::<Person>().await.unwrap();
+
+    // Registering again leaves the existing indexes in place.
+    backend.register::<Person>().await.unwrap();
+
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "select name from sqlite_master where type = 'index' and tbl_name = 'Person' and sql is not null order by name",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    println!("{indexes:#?}");
+    // [
+    //     "Person_age_index",
+    //     "Person_email_unique",
+    //     "Person_first_name_last_name_unique",
+    // ]
+    assert_eq!(
+        indexes,
+        vec![
+            "Person_age_index",
+            "Person_email_unique",
+            "Person_first_name_last_name_unique",
+        ]
+    );
+
+    backend
+        .insert(
+            &1,
+            &Person {
+                first_name: "Ada".to_string(),
+                last_name: "Lovelace".to_string(),
+                age: 36,
+                email: "ada@example.com".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    // Filters on indexed columns can be served by their index.
+    let adults = backend
+        .list::<Person>()
+        .filter(Person::FIELDS.age.ge(18))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(adults, vec![1]);
+
+    let plan: Vec<(i64, i64, i64, String)> =
+        sqlx::query_as("explain query plan select entity from Person where age >= 18")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert!(plan
+        .iter()
+        .any(|(_, _, _, detail)| detail.contains("Person_age_index")));
+
+    // Unique indexes reject duplicate values, failing the insert.
+    let duplicate = backend
+        .insert(
+            &2,
+            &Person {
+                first_name: "Ada".to_string(),
+                last_name: "Byron".to_string(),
+                age: 17,
+                email: "ada@example.com".to_string(),
+            },
+        )
+        .await;
+
+    let Err(sqlx::Error::Database(error)) = duplicate else {
+        panic!("expected a unique violation, got {duplicate:?}");
+    };
+    assert!(error.is_unique_violation());
+    assert!(backend.get::<Person>(&2).await.is_err());
+}
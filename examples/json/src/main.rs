@@ -0,0 +1,92 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Preferences {
+    theme: String,
+    font_size: i64,
+    shortcuts: Vec<String>,
+}
+
+// Preferences are stored as a single JSON document, rather than a column per field.
+#[derive(Component, Debug, PartialEq)]
+pub struct Settings {
+    user: String,
+    #[erm(json)]
+    prefs: Preferences,
+}
+
+#[tokio::main]
+async fn main() {
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Settings>().await.unwrap();
+
+    let alice = 1;
+    backend
+        .insert(
+            &alice,
+            &Settings {
+                user: "alice".to_string(),
+                prefs: Preferences {
+                    theme: "dark".to_string(),
+                    font_size: 14,
+                    shortcuts: vec!["ctrl+s".to_string()],
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+    let bob = 2;
+    backend
+        .insert(
+            &bob,
+            &Settings {
+                user: "bob".to_string(),
+                prefs: Preferences {
+                    theme: "light".to_string(),
+                    font_size: 18,
+                    shortcuts: vec![],
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+    let settings = backend.get::<Settings>(&alice).await.unwrap();
+    assert_eq!(settings.prefs.shortcuts, vec!["ctrl+s".to_string()]);
+
+    // Filter on values within the documents.
+    let dark = backend
+        .list::<Settings>()
+        .filter(
+            Settings::FIELDS
+                .prefs
+                .json_path("$.theme")
+                .eq("dark".to_string()),
+        )
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{dark:#?}");
+    // [
+    //     1,
+    // ]
+    assert_eq!(dark, vec![alice]);
+
+    let large = backend
+        .list::<Settings>()
+        .filter(Settings::FIELDS.prefs.json_path("$.font_size").ge(16))
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(large.len(), 1);
+    assert_eq!(large[0].user, "bob");
+}
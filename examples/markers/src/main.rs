@@ -29,22 +29,26 @@ async fn main() {
     let elephant = 1;
     backend
         .insert(&elephant, &(Name("Elephant".to_string()), Legs(4), Animal))
-        .await;
+        .await
+        .unwrap();
 
     let snake = 2;
     backend
         .insert(&snake, &(Name("Snake".to_string()), Animal))
-        .await;
+        .await
+        .unwrap();
 
     let stool = 3;
     backend
         .insert(&stool, &(Name("Stool".to_string()), Legs(3), Furniture))
-        .await;
+        .await
+        .unwrap();
 
     let table = 4;
     backend
         .insert(&table, &(Name("Table".to_string()), Legs(4), Furniture))
-        .await;
+        .await
+        .unwrap();
 
     let legged_things = backend
         .list::<Name>()
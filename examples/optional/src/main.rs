@@ -7,6 +7,16 @@ pub struct Name(String);
 #[derive(Component, Debug, PartialEq)]
 pub struct Age(i64);
 
+#[derive(Component, Debug, PartialEq)]
+pub struct Inventory {
+    gold: i64,
+    slots: i64,
+}
+
+// Marker components have no columns of their own.
+#[derive(Component, Debug, PartialEq)]
+pub struct Admin;
+
 #[tokio::main]
 async fn main() {
     // Create an Sqlite backend using u64 as entity IDs
@@ -15,6 +25,8 @@ async fn main() {
     // This creates the component tables where data will be persisted.
     backend.register::<Name>().await.unwrap();
     backend.register::<Age>().await.unwrap();
+    backend.register::<Inventory>().await.unwrap();
+    backend.register::<Admin>().await.unwrap();
 
     // Create our entities: Jimothy and Andrea
     //
@@ -23,11 +35,15 @@ async fn main() {
     let jimothy = 1;
     backend
         .insert(&jimothy, &(Name("Jimothy".to_string()), Age(10)))
-        .await;
+        .await
+        .unwrap();
 
     // It's rude to ask a woman her age!
     let andrea = 2;
-    backend.insert(&andrea, &(Name("Andrea".to_string()))).await;
+    backend
+        .insert(&andrea, &(Name("Andrea".to_string())))
+        .await
+        .unwrap();
 
     // Let's name an Archetype instead of just relying on a tuple.
     #[derive(Archetype, Debug)]
@@ -65,4 +81,55 @@ async fn main() {
     //         age: None,
     //     },
     // ]
+
+    // Archetypes can be nested, and contain optional components of any shape.
+    #[derive(Archetype, Debug)]
+    struct Player {
+        person: Person,
+        inventory: Option<Inventory>,
+        admin: Option<Admin>,
+    }
+
+    // Optional components are skipped when inserting None.
+    let barbara = 3;
+    backend
+        .insert(
+            &barbara,
+            &Player {
+                person: Person {
+                    name: Name("Barbara".to_string()),
+                    age: Some(Age(40)),
+                },
+                inventory: None,
+                admin: Some(Admin),
+            },
+        )
+        .await
+        .unwrap();
+
+    let player = backend.get::<Player>(&barbara).await.unwrap();
+    assert_eq!(player.person.name.0, "Barbara");
+    assert_eq!(player.person.age, Some(Age(40)));
+    assert_eq!(player.inventory, None);
+    assert_eq!(player.admin, Some(Admin));
+
+    backend
+        .insert(&barbara, &Inventory { gold: 10, slots: 4 })
+        .await
+        .unwrap();
+
+    let player = backend.get::<Player>(&barbara).await.unwrap();
+    assert_eq!(player.inventory, Some(Inventory { gold: 10, slots: 4 }));
+
+    // Jimothy has neither inventory nor admin rights.
+    let player = backend.get::<Player>(&jimothy).await.unwrap();
+    assert_eq!(player.person.age, Some(Age(10)));
+    assert_eq!(player.inventory, None);
+    assert_eq!(player.admin, None);
+
+    // Removing the Player removes all of its components, including nested ones.
+    backend.remove::<Player>(&barbara).await.unwrap();
+    assert!(backend.get::<Name>(&barbara).await.is_err());
+    assert!(backend.get::<Inventory>(&barbara).await.is_err());
+    assert!(backend.get::<Admin>(&barbara).await.is_err());
 }
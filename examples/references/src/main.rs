@@ -0,0 +1,138 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Player;
+
+#[derive(Component, Debug)]
+pub struct Npc;
+
+// Owner points to another entity, which must have a Name component.
+#[derive(Component, Debug)]
+pub struct Owner(#[erm(references = Name)] EntityRef<i64>);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // Referenced tables must be registered before the tables referencing them.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Player>().await.unwrap();
+    backend.register::<Npc>().await.unwrap();
+    backend.register::<Owner>().await.unwrap();
+
+    let alice = 1;
+    backend
+        .insert(&alice, &(Name("Alice".to_string()), Player))
+        .await
+        .unwrap();
+
+    let shopkeeper = 2;
+    backend
+        .insert(&shopkeeper, &(Name("Shopkeeper".to_string()), Npc))
+        .await
+        .unwrap();
+
+    backend
+        .insert(&3, &(Name("Sword".to_string()), Owner(EntityRef(alice))))
+        .await
+        .unwrap();
+
+    backend
+        .insert(
+            &4,
+            &(Name("Shield".to_string()), Owner(EntityRef(shopkeeper))),
+        )
+        .await
+        .unwrap();
+
+    // List all items owned by players.
+    let player_items = backend
+        .list::<(Name, Owner)>()
+        .join_ref(Owner::FIELDS.self_0, |owner| owner.with::<Player>())
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{player_items:#?}");
+    // [
+    //     (
+    //         Name(
+    //             "Sword",
+    //         ),
+    //         Owner(
+    //             EntityRef(
+    //                 1,
+    //             ),
+    //         ),
+    //     ),
+    // ]
+    assert_eq!(player_items.len(), 1);
+    assert_eq!(player_items[0].1 .0, EntityRef(alice));
+
+    // Fetch the name of the owner through the reference.
+    let named_items = backend
+        .list::<(Name, Owner)>()
+        .fetch_ref::<Name, _>(Owner::FIELDS.self_0, |owner| owner.with::<Player>())
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{named_items:#?}");
+    // [
+    //     Dereferenced {
+    //         components: (
+    //             Name(
+    //                 "Sword",
+    //             ),
+    //             Owner(
+    //                 EntityRef(
+    //                     1,
+    //                 ),
+    //             ),
+    //         ),
+    //         referenced: Name(
+    //             "Alice",
+    //         ),
+    //     },
+    // ]
+    assert_eq!(named_items.len(), 1);
+    assert_eq!(named_items[0].components().0 .0, "Sword");
+    assert_eq!(named_items[0].referenced().0, "Alice");
+
+    // Without a filter, every item is returned along with the name of its owner.
+    let mut owners = backend
+        .list::<(Name, Owner)>()
+        .fetch_ref::<Name, _>(Owner::FIELDS.self_0, |owner| owner)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    owners.sort_by(|a, b| a.components().0 .0.cmp(&b.components().0 .0));
+
+    let owners: Vec<_> = owners
+        .into_iter()
+        .map(|item| {
+            let ((item, _), owner) = item.into_inner();
+            (item.0, owner.0)
+        })
+        .collect();
+
+    assert_eq!(
+        owners,
+        vec![
+            ("Shield".to_string(), "Shopkeeper".to_string()),
+            ("Sword".to_string(), "Alice".to_string())
+        ]
+    );
+}
@@ -0,0 +1,141 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Radius(f64);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Size {
+    width: f64,
+    height: f64,
+}
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Color(String);
+
+#[derive(Archetype, Debug, PartialEq)]
+pub struct Circle {
+    radius: Radius,
+}
+
+#[derive(Archetype, Debug, PartialEq)]
+pub struct Rect {
+    size: Size,
+    color: Option<Color>,
+}
+
+// Entities are returned as whichever variant's components they have,
+// or skipped if they match none of them. Variants can't share components,
+// so common ones such as Name are listed alongside the enum instead.
+#[derive(Archetype, Debug, PartialEq)]
+pub enum Shape {
+    Circle(Circle),
+    Rect(Rect),
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Radius>().await.unwrap();
+    backend.register::<Size>().await.unwrap();
+    backend.register::<Color>().await.unwrap();
+
+    let wheel = 1;
+    backend
+        .insert(
+            &wheel,
+            &(
+                Name("Wheel".to_string()),
+                Shape::Circle(Circle {
+                    radius: Radius(0.5),
+                }),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let door = 2;
+    backend
+        .insert(
+            &door,
+            &(
+                Name("Door".to_string()),
+                Shape::Rect(Rect {
+                    size: Size {
+                        width: 1.0,
+                        height: 2.0,
+                    },
+                    color: Some(Color("Red".to_string())),
+                }),
+            ),
+        )
+        .await
+        .unwrap();
+
+    // Named entities without a shape are not listed.
+    let ghost = 3;
+    backend
+        .insert(&ghost, &Name("Ghost".to_string()))
+        .await
+        .unwrap();
+
+    let shapes = backend
+        .list::<(Name, Shape)>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{shapes:#?}");
+    assert_eq!(
+        shapes,
+        vec![
+            (
+                Name("Wheel".to_string()),
+                Shape::Circle(Circle {
+                    radius: Radius(0.5),
+                }),
+            ),
+            (
+                Name("Door".to_string()),
+                Shape::Rect(Rect {
+                    size: Size {
+                        width: 1.0,
+                        height: 2.0,
+                    },
+                    color: Some(Color("Red".to_string())),
+                }),
+            ),
+        ]
+    );
+
+    // Updates apply to the components of the current variant.
+    backend
+        .update(
+            &wheel,
+            &Shape::Circle(Circle {
+                radius: Radius(0.75),
+            }),
+        )
+        .await
+        .unwrap();
+
+    let shape = backend.get::<Shape>(&wheel).await.unwrap();
+    assert_eq!(
+        shape,
+        Shape::Circle(Circle {
+            radius: Radius(0.75),
+        })
+    );
+
+    // Removing a shape removes the components of every variant.
+    backend.remove::<Shape>(&door).await.unwrap();
+    assert!(backend.get::<Shape>(&door).await.is_err());
+}
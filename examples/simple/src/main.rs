@@ -23,12 +23,14 @@ async fn main() {
     let jimothy = 1;
     backend
         .insert(&jimothy, &(Name("Jimothy".to_string()), Age(10)))
-        .await;
+        .await
+        .unwrap();
 
     let andrea = 2;
     backend
         .insert(&andrea, &(Name("Andrea".to_string()), Age(32)))
-        .await;
+        .await
+        .unwrap();
 
     // Let's name an Archetype instead of just relying on a tuple.
     #[derive(Archetype, Debug)]
@@ -67,7 +69,7 @@ async fn main() {
     //     },
     // ]
 
-    backend.remove::<Person>(&jimothy).await;
+    backend.remove::<Person>(&jimothy).await.unwrap();
 
     let remaining_names = backend
         .list::<Name>()
@@ -86,4 +88,32 @@ async fn main() {
 
     // Fetch Andrea's age
     assert_eq!(backend.get::<Age>(&andrea).await.unwrap(), Age(32));
+
+    // Named archetypes can be inserted and updated just like tuples.
+    let bartholomew = 3;
+    backend
+        .insert(
+            &bartholomew,
+            &Person {
+                name: Name("Bartholomew".to_string()),
+                age: Age(55),
+            },
+        )
+        .await
+        .unwrap();
+
+    backend
+        .update(
+            &bartholomew,
+            &Person {
+                name: Name("Bart".to_string()),
+                age: Age(56),
+            },
+        )
+        .await
+        .unwrap();
+
+    let bart = backend.get::<Person>(&bartholomew).await.unwrap();
+    assert_eq!(bart.name.0, "Bart");
+    assert_eq!(bart.age, Age(56));
 }
@@ -0,0 +1,129 @@
+use erm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Removed accounts are only marked as deleted, so they can be restored later.
+#[derive(Component, Debug, PartialEq)]
+#[erm(soft_delete)]
+pub struct Account {
+    email: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // The Account table gets an additional deleted_at column.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Account>().await.unwrap();
+
+    let alice = 1;
+    backend
+        .insert(
+            &alice,
+            &(
+                Name("Alice".to_string()),
+                Account {
+                    email: "alice@example.com".to_string(),
+                },
+            ),
+        )
+        .await
+        .unwrap();
+
+    let bob = 2;
+    backend
+        .insert(
+            &bob,
+            &(
+                Name("Bob".to_string()),
+                Account {
+                    email: "bob@example.com".to_string(),
+                },
+            ),
+        )
+        .await
+        .unwrap();
+
+    // Bob closes his account, which marks the row as deleted.
+    backend.remove::<Account>(&bob).await.unwrap();
+
+    let accounts = backend
+        .list::<(Name, Account)>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{accounts:#?}");
+    // [
+    //     (
+    //         Name(
+    //             "Alice",
+    //         ),
+    //         Account {
+    //             email: "alice@example.com",
+    //         },
+    //     ),
+    // ]
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].0, Name("Alice".to_string()));
+    assert!(backend.get::<Account>(&bob).await.is_err());
+
+    // Updates don't apply to deleted accounts either.
+    backend
+        .update(
+            &bob,
+            &Account {
+                email: "robert@example.com".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    // Deleted accounts can still be found when asked for.
+    let accounts = backend
+        .list::<Account>()
+        .include_deleted()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(accounts.len(), 2);
+
+    // Bob changed his mind.
+    backend.restore::<Account>(&bob).await.unwrap();
+
+    let account = backend.get::<Account>(&bob).await.unwrap();
+    assert_eq!(
+        account,
+        Account {
+            email: "bob@example.com".to_string(),
+        }
+    );
+
+    // Inserting over a deleted account replaces it.
+    backend.remove::<Account>(&alice).await.unwrap();
+    backend
+        .insert(
+            &alice,
+            &Account {
+                email: "alice@example.org".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let account = backend.get::<Account>(&alice).await.unwrap();
+    assert_eq!(
+        account,
+        Account {
+            email: "alice@example.org".to_string(),
+        }
+    );
+}
@@ -0,0 +1,161 @@
+use erm::prelude::*;
+use erm::subscription::Event;
+use futures::{SinkExt as _, StreamExt as _};
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Health(i64);
+
+// Items are stored in the `Inventory_items` table, one row per item.
+#[derive(Component, Debug)]
+#[erm(track_changes)]
+pub struct Inventory {
+    #[erm(collection)]
+    items: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Health>().await.unwrap();
+    backend.register::<Inventory>().await.unwrap();
+
+    let player = 1;
+    backend
+        .insert(&player, &(Name("Player".to_string()), Health(100)))
+        .await
+        .unwrap();
+
+    // Watch for wounded entities in the background, forwarding
+    // a description of each event back to us.
+    let subscription = backend
+        .list::<Health>()
+        .filter(Health::FIELDS.self_0.less_than(50))
+        .subscribe()
+        .await
+        .unwrap();
+
+    let (mut sender, mut events) = futures::channel::mpsc::unbounded();
+    tokio::spawn(async move {
+        let mut subscription = std::pin::pin!(subscription);
+
+        while let Some(event) = subscription.next().await {
+            let description = match event.unwrap() {
+                Event::Inserted(entity) => format!("entity {} is wounded", entity.id()),
+                Event::Updated(entity) => format!("entity {} is still wounded", entity.id()),
+                Event::Removed(entity) => format!("entity {entity} is no longer wounded"),
+            };
+
+            sender.send(description).await.unwrap();
+        }
+    });
+
+    // Subscriptions re-evaluate the query when notified, so wait for each
+    // event before moving on, to observe every intermediate state.
+    let monster = 2;
+    backend
+        .insert(&monster, &(Name("Monster".to_string()), Health(20)))
+        .await
+        .unwrap();
+    let monster_wounded = events.next().await.unwrap();
+
+    backend.update(&player, &Health(10)).await.unwrap();
+    let player_wounded = events.next().await.unwrap();
+
+    backend.update(&monster, &Health(5)).await.unwrap();
+    let monster_still_wounded = events.next().await.unwrap();
+
+    backend.update(&player, &Health(80)).await.unwrap();
+    let player_healed = events.next().await.unwrap();
+
+    // The player no longer matches the query, so losing its health goes unnoticed.
+    backend.remove::<Health>(&player).await.unwrap();
+    backend.remove::<Health>(&monster).await.unwrap();
+    let monster_removed = events.next().await.unwrap();
+
+    println!("{monster_wounded}\n{player_wounded}\n{monster_still_wounded}\n{player_healed}\n{monster_removed}");
+    // entity 2 is wounded
+    // entity 1 is wounded
+    // entity 2 is still wounded
+    // entity 1 is no longer wounded
+    // entity 2 is no longer wounded
+    assert_eq!(monster_wounded, "entity 2 is wounded");
+    assert_eq!(player_wounded, "entity 1 is wounded");
+    assert_eq!(monster_still_wounded, "entity 2 is still wounded");
+    assert_eq!(player_healed, "entity 1 is no longer wounded");
+    assert_eq!(monster_removed, "entity 2 is no longer wounded");
+
+    // Subscriptions watch the tables of the components they read, including the tables
+    // holding the items of collections, so changes to the items are noticed as well.
+    let subscription = backend
+        .list::<Inventory>()
+        .changed::<Inventory>(0)
+        .subscribe()
+        .await
+        .unwrap();
+    let mut subscription = std::pin::pin!(subscription);
+
+    backend
+        .insert(
+            &player,
+            &Inventory {
+                items: vec!["Sword".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+
+    // The inventory and its item are inserted separately, so the entity is first inserted,
+    // then updated, and both events observe the complete inventory.
+    let Some(Ok(Event::Inserted(inserted))) = subscription.next().await else {
+        panic!("expected the inventory to be inserted");
+    };
+    let Some(Ok(Event::Updated(updated))) = subscription.next().await else {
+        panic!("expected the inventory to be updated");
+    };
+
+    assert_eq!(inserted.items, vec!["Sword"]);
+    assert_eq!(updated.items, vec!["Sword"]);
+
+    // Sqlite only keeps the most recent events for subscriptions to catch up on, so a
+    // subscription which falls too far behind evaluates its query again instead.
+    backend
+        .insert(
+            &monster,
+            &Inventory {
+                items: vec!["Shield".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+    backend.remove::<Inventory>(&player).await.unwrap();
+
+    for _ in 0..500 {
+        backend
+            .update(
+                &monster,
+                &Inventory {
+                    items: vec!["Shield".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    let Some(Ok(Event::Inserted(inserted))) = subscription.next().await else {
+        panic!("expected the monster's inventory to be inserted");
+    };
+    let Some(Ok(Event::Removed(removed))) = subscription.next().await else {
+        panic!("expected the player's inventory to be removed");
+    };
+
+    assert_eq!(*inserted.id(), monster);
+    assert_eq!(inserted.items, vec!["Shield"]);
+    assert_eq!(removed, player);
+}
@@ -0,0 +1,98 @@
+use std::num::NonZeroU32;
+
+use erm::prelude::*;
+use sqlx::sqlite::SqlitePoolOptions;
+
+/// Email address, which can only be constructed if it contains an `@`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email(String);
+
+impl TryFrom<String> for Email {
+    type Error = String;
+
+    fn try_from(address: String) -> Result<Self, Self::Error> {
+        if address.contains('@') {
+            Ok(Email(address))
+        } else {
+            Err(format!("`{address}` is not an email address"))
+        }
+    }
+}
+
+impl From<&Email> for String {
+    fn from(email: &Email) -> Self {
+        email.0.clone()
+    }
+}
+
+/// Number of seats of an account, which has at least one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seats(NonZeroU32);
+
+impl TryFrom<u32> for Seats {
+    type Error = String;
+
+    fn try_from(seats: u32) -> Result<Self, Self::Error> {
+        NonZeroU32::new(seats)
+            .map(Seats)
+            .ok_or_else(|| "an account needs at least one seat".to_string())
+    }
+}
+
+impl From<&Seats> for u32 {
+    fn from(seats: &Seats) -> Self {
+        seats.0.get()
+    }
+}
+
+// Stored values are validated when read, rather than trusting the database.
+#[derive(Component, Debug, PartialEq)]
+pub struct Account {
+    #[erm(intermediate = String, try)]
+    email: Email,
+    #[erm(intermediate = u32, try)]
+    seats: Seats,
+}
+
+#[tokio::main]
+async fn main() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let backend: SqliteBackend<i64> = SqliteBackend::new(pool.clone());
+    backend.register::<Account>().await.unwrap();
+
+    let acme = 1;
+    backend
+        .insert(
+            &acme,
+            &Account {
+                email: Email::try_from("billing@acme.com".to_string()).unwrap(),
+                seats: Seats::try_from(5).unwrap(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let account = backend.get::<Account>(&acme).await.unwrap();
+    assert_eq!(account.seats, Seats::try_from(5).unwrap());
+
+    // Someone edited the database by hand.
+    let broken = 2;
+    sqlx::query("insert into Account(entity, email, seats) values (?, 'nobody', 0)")
+        .bind(broken)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let error = backend.get::<Account>(&broken).await.unwrap_err();
+    println!("{error}");
+    // error occurred while decoding: invalid value for Account.email: `nobody` is not an email address
+    assert!(matches!(error, sqlx::Error::Decode(_)));
+    assert!(error.to_string().contains("Account.email"));
+}
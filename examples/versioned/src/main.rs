@@ -0,0 +1,93 @@
+use erm::prelude::*;
+use erm::version::{UpdateError, Version};
+
+// Every update bumps the version of a Balance, so concurrent updates can be detected.
+#[derive(Component, Debug, PartialEq)]
+#[erm(versioned)]
+pub struct Balance {
+    amount: i64,
+}
+
+// Versioned components can track changes as well.
+#[derive(Component, Debug, PartialEq)]
+#[erm(versioned, track_changes)]
+pub struct Stock {
+    count: i64,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // The Balance table gets an additional version column.
+    backend.register::<Balance>().await.unwrap();
+    backend.register::<Stock>().await.unwrap();
+
+    let account = 1;
+    backend
+        .insert(&account, &Balance { amount: 100 })
+        .await
+        .unwrap();
+
+    // Two workers read the balance at the same time.
+    let mut first = backend.get::<Version<Balance>>(&account).await.unwrap();
+    let mut second = backend.get::<Version<Balance>>(&account).await.unwrap();
+    assert_eq!(first.version(), 1);
+
+    // The first worker deposits 50.
+    first.amount += 50;
+    let version = backend.update_versioned(&account, &first).await.unwrap();
+    assert_eq!(version, 2);
+
+    // The second worker withdraws 30, but is working on an outdated balance.
+    second.amount -= 30;
+    let result = backend.update_versioned(&account, &second).await;
+    assert!(matches!(result, Err(UpdateError::Conflict)));
+
+    // After reading the balance again, the withdrawal goes through.
+    let mut second = backend.get::<Version<Balance>>(&account).await.unwrap();
+    second.amount -= 30;
+    backend.update_versioned(&account, &second).await.unwrap();
+
+    let balance = backend.get::<Version<Balance>>(&account).await.unwrap();
+    println!("{balance:#?}");
+    // Version {
+    //     version: 3,
+    //     component: Balance {
+    //         amount: 120,
+    //     },
+    // }
+    assert_eq!(balance, Version::new(3, Balance { amount: 120 }));
+
+    // Regular updates overwrite the balance regardless, but still bump its version.
+    backend
+        .update(&account, &Balance { amount: 0 })
+        .await
+        .unwrap();
+
+    let balance = backend.get::<Version<Balance>>(&account).await.unwrap();
+    assert_eq!(balance, Version::new(4, Balance { amount: 0 }));
+
+    // Reading just the component works as usual.
+    let balance = backend.get::<Balance>(&account).await.unwrap();
+    assert_eq!(balance, Balance { amount: 0 });
+
+    // Conflicts are detected regardless of the change ticks advanced by the update.
+    backend
+        .insert(&account, &Stock { count: 10 })
+        .await
+        .unwrap();
+    let mut first = backend.get::<Version<Stock>>(&account).await.unwrap();
+    let mut second = backend.get::<Version<Stock>>(&account).await.unwrap();
+
+    first.count -= 1;
+    backend.update_versioned(&account, &first).await.unwrap();
+
+    second.count -= 5;
+    let result = backend.update_versioned(&account, &second).await;
+    assert!(matches!(result, Err(UpdateError::Conflict)));
+
+    let stock = backend.get::<Version<Stock>>(&account).await.unwrap();
+    assert_eq!(stock, Version::new(2, Stock { count: 9 }));
+}
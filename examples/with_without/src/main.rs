@@ -25,7 +25,10 @@ async fn main() {
     backend.register::<ShippedTo>().await.unwrap();
 
     // Outstanding order, which has been neither paid nor shipped..
-    backend.insert(&1, &OrderId("#1234".to_string())).await;
+    backend
+        .insert(&1, &OrderId("#1234".to_string()))
+        .await
+        .unwrap();
 
     // This one has been paid, but not yet shipped.
     backend
@@ -38,7 +41,8 @@ async fn main() {
                 },
             ),
         )
-        .await;
+        .await
+        .unwrap();
 
     // This order has been ordered, paid and shipped.
     backend
@@ -54,7 +58,8 @@ async fn main() {
                 },
             ),
         )
-        .await;
+        .await
+        .unwrap();
 
     // List all items that have been shipped
     let shipped_items = backend